@@ -0,0 +1,58 @@
+//! Compiles every shader crate ahead of time, for the targets that can't run
+//! `rustc_codegen_spirv` themselves (wasm and Android).
+//!
+//! `egui_demo_app/build.rs` runs this, and it writes `embedded_shaders.rs` into that build
+//! script's `OUT_DIR`, embedding the compiled modules with `include_bytes!`.
+
+use std::{
+    error::Error,
+    fmt::Write,
+    path::{Path, PathBuf},
+};
+
+use spirv_builder::{MetadataPrintout, ModuleResult, SpirvBuilder};
+
+// Shared with the app, which has to find the same crates natively.
+#[path = "../../egui_demo_app/src/apps/shader_discovery.rs"]
+mod shader_discovery;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let out_dir = std::env::var_os("OUT_DIR")
+        .map(PathBuf::from)
+        .ok_or("OUT_DIR is not set, this is meant to be run by egui_demo_app/build.rs")?;
+    let shaders_path = Path::new(env!("CARGO_MANIFEST_DIR")).join("../shaders");
+    // Pick up added and removed shader crates. Changes to the sources of the existing ones are
+    // reported by `SpirvBuilder` below.
+    println!("cargo:rerun-if-changed={}", shaders_path.display());
+
+    let crate_names = shader_discovery::discover_shader_crates(&shaders_path)?;
+
+    let mut generated = String::from(
+        "// Generated by egui_demo_app_builder.\n\n\
+         /// The compiled modules of every shader crate, by entry point.\n\
+         pub const EMBEDDED_SHADERS: &[(&str, &[(&str, &[u8])])] = &[\n",
+    );
+    for crate_name in &crate_names {
+        // A module per entry point, as on devices without push constants even modules that
+        // merely declare them are rejected.
+        let compile_result =
+            SpirvBuilder::new(shaders_path.join(crate_name), "spirv-unknown-vulkan1.1")
+                .print_metadata(MetadataPrintout::DependencyOnly)
+                .multimodule(true)
+                .build()?;
+        let ModuleResult::MultiModule(modules) = compile_result.module else {
+            unreachable!("multimodule builds produce a module per entry point");
+        };
+        writeln!(generated, "    ({crate_name:?}, &[")?;
+        for (entry_point, path) in modules {
+            writeln!(
+                generated,
+                "        ({entry_point:?}, include_bytes!({path:?})),"
+            )?;
+        }
+        writeln!(generated, "    ]),")?;
+    }
+    generated += "];\n";
+    std::fs::write(out_dir.join("embedded_shaders.rs"), generated)?;
+    Ok(())
+}
@@ -29,16 +29,22 @@ fn main() -> Result<(), Box<dyn Error>> {
     // `spirv-builder` generates in a similar way from `$OUT_DIR` and `$PROFILE`,
     // otherwise repeated `cargo build`s will cause build script reruns and the
     // rebuilding of `rustc_codegen_spirv` (likely due to common proc macro deps).
-    let dir = dir.join("example-runner-wgpu-builder");
-    let status = std::process::Command::new("cargo")
+    let dir = dir.join("egui_demo_app_builder");
+    let mut command = std::process::Command::new("cargo");
+    command
         .args([
             "run",
             "--release",
             "-p",
-            "example-runner-wgpu-builder",
+            "egui_demo_app_builder",
             "--target-dir",
         ])
-        .arg(dir)
+        .arg(dir);
+    // Build the shaders with the same tools as this crate would.
+    if env::var_os("CARGO_FEATURE_USE_INSTALLED_TOOLS").is_some() {
+        command.args(["--no-default-features", "--features", "use-installed-tools"]);
+    }
+    let status = command
         .env_remove("CARGO_ENCODED_RUSTFLAGS")
         .stderr(std::process::Stdio::inherit())
         .stdout(std::process::Stdio::inherit())
@@ -0,0 +1,142 @@
+//! Navigation for 3D shaders, producing the [`Camera`] they are rendered with.
+
+use shared::camera::Camera;
+use spirv_std::glam::{vec3, Mat4, Vec3};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum CameraMode {
+    /// Dragging turns the camera around `CameraController::target`.
+    Orbit,
+
+    /// Dragging turns the camera around itself.
+    Fly,
+}
+
+/// A camera looking at `target` from `distance` away, in the direction given by `yaw` and
+/// `pitch` (in radians).
+pub struct CameraController {
+    pub mode: CameraMode,
+    pub target: Vec3,
+    pub yaw: f32,
+    pub pitch: f32,
+    pub distance: f32,
+
+    /// Vertical field of view, in degrees.
+    pub fov_y: f32,
+
+    /// How fast WASD moves the camera, in world units per second.
+    pub speed: f32,
+}
+
+impl Default for CameraController {
+    fn default() -> Self {
+        Self {
+            mode: CameraMode::Orbit,
+            target: vec3(0.0, 0.8, 0.0),
+            yaw: 0.0,
+            pitch: 0.4,
+            distance: 6.0,
+            fov_y: 60.0,
+            speed: 3.0,
+        }
+    }
+}
+
+/// Radians per physical pixel dragged.
+const ROTATE_SPEED: f32 = 0.005;
+
+/// Keep away from straight up and down, where yaw stops making sense.
+const MAX_PITCH: f32 = 1.55;
+
+const NEAR: f32 = 0.01;
+const FAR: f32 = 1000.0;
+
+impl CameraController {
+    /// The direction the camera looks in.
+    pub fn forward(&self) -> Vec3 {
+        let (sin_yaw, cos_yaw) = self.yaw.sin_cos();
+        let (sin_pitch, cos_pitch) = self.pitch.sin_cos();
+        // Yaw 0 looks along +z, positive pitch looks down.
+        vec3(-cos_pitch * sin_yaw, -sin_pitch, cos_pitch * cos_yaw)
+    }
+
+    pub fn eye(&self) -> Vec3 {
+        self.target - self.distance * self.forward()
+    }
+
+    /// Turns the camera by a drag of `delta` physical pixels.
+    pub fn rotate(&mut self, delta: egui::Vec2) {
+        let eye = self.eye();
+        self.yaw += ROTATE_SPEED * delta.x;
+        self.pitch = (self.pitch + ROTATE_SPEED * delta.y).clamp(-MAX_PITCH, MAX_PITCH);
+        if self.mode == CameraMode::Fly {
+            // Look around from where the camera is, rather than around the target.
+            self.target = eye + self.distance * self.forward();
+        }
+    }
+
+    /// Zooms by `factor` (> 1 zooms in).
+    pub fn zoom(&mut self, factor: f32) {
+        match self.mode {
+            CameraMode::Orbit => self.distance = (self.distance / factor).clamp(NEAR, FAR / 2.0),
+            CameraMode::Fly => self.target += self.forward() * self.distance * (factor - 1.0),
+        }
+    }
+
+    /// Moves the camera with WASD (and Q/E for down/up), faster while shift is held.
+    pub fn update_keys(&mut self, input: &egui::InputState) {
+        let forward = self.forward();
+        let right = forward.cross(Vec3::Y).normalize_or_zero();
+        let forward = match self.mode {
+            // Stay at the same height while orbiting.
+            CameraMode::Orbit => vec3(forward.x, 0.0, forward.z).normalize_or_zero(),
+            CameraMode::Fly => forward,
+        };
+        let mut direction = Vec3::ZERO;
+        for (key, axis) in [
+            (egui::Key::W, forward),
+            (egui::Key::S, -forward),
+            (egui::Key::D, right),
+            (egui::Key::A, -right),
+            (egui::Key::E, Vec3::Y),
+            (egui::Key::Q, -Vec3::Y),
+        ] {
+            if input.key_down(key) {
+                direction += axis;
+            }
+        }
+        let speed = if input.modifiers.shift {
+            4.0 * self.speed
+        } else {
+            self.speed
+        };
+        self.target += direction.normalize_or_zero() * speed * input.stable_dt.min(0.1);
+    }
+
+    /// The uniforms for rendering into `viewport`, in physical pixels of the framebuffer.
+    pub fn camera(&self, viewport: egui::Rect) -> Camera {
+        let eye = self.eye();
+        let forward = self.forward();
+        let right = forward.cross(Vec3::Y).normalize_or_zero();
+        let up = right.cross(forward);
+        let fov_y = self.fov_y.to_radians();
+        let aspect_ratio = viewport.width() / viewport.height().max(1.0);
+        let view = Mat4::look_at_rh(eye, self.target, Vec3::Y);
+        let projection = Mat4::perspective_rh(fov_y, aspect_ratio, NEAR, FAR);
+        Camera {
+            view_projection: projection * view,
+            eye: eye.extend(1.0),
+            right: right.extend(0.0),
+            up: up.extend(0.0),
+            forward: forward.extend(0.0),
+            viewport: [
+                viewport.min.x,
+                viewport.min.y,
+                viewport.width(),
+                viewport.height(),
+            ]
+            .into(),
+            projection: [(0.5 * fov_y).tan(), NEAR, FAR, 0.0].into(),
+        }
+    }
+}
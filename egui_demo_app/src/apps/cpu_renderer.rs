@@ -0,0 +1,138 @@
+//! Renders shader crates without a GPU, by calling their fragment entry points directly.
+//!
+//! The shader crates compile for the host too, so `main_fs` is just a Rust function that can
+//! be evaluated once per pixel. This is far slower than the GPU, but it works everywhere,
+//! including machines without any wgpu adapter.
+
+use std::collections::{hash_map::Entry, HashMap};
+
+use shared::{camera::Camera, ShaderConstants};
+use spirv_std::glam::{vec4, Vec2, Vec4};
+
+/// A `main_fs` entry point, taking every resource the host binds whether it uses it or not.
+pub type FragmentShader = fn(Vec4, &ShaderConstants, &[Vec2], &Camera, &mut Vec4);
+
+/// The host build of the `main_fs` entry point of `crate_name`, if it is linked in.
+pub fn fragment_shader(crate_name: &str) -> Option<FragmentShader> {
+    match crate_name {
+        "mandelbrot" => Some(|frag_coord, constants, reference_orbit, _, output| {
+            mandelbrot::main_fs(frag_coord, constants, reference_orbit, output)
+        }),
+        "raymarch" => Some(|frag_coord, constants, _, camera, output| {
+            raymarch::main_fs(frag_coord, constants, camera, output)
+        }),
+        _ => None,
+    }
+}
+
+/// Evaluates `shader` for every pixel of a `size` image, spread over all available cores
+/// (except on the web, which renders on the calling thread).
+///
+/// `origin` is the `frag_coord` of the top left corner of the image, as the image is
+/// usually only part of the framebuffer that the GPU would render to.
+pub fn render(
+    shader: FragmentShader,
+    constants: &ShaderConstants,
+    reference_orbit: &[[f32; 2]],
+    camera: &Camera,
+    origin: Vec2,
+    size: [usize; 2],
+) -> egui::ColorImage {
+    let [width, height] = size;
+    let mut image = egui::ColorImage::new(size, egui::Color32::TRANSPARENT);
+    if width == 0 || height == 0 {
+        return image;
+    }
+    let reference_orbit: Vec<Vec2> = reference_orbit.iter().copied().map(Vec2::from).collect();
+    let reference_orbit = reference_orbit.as_slice();
+    let render_rows = |first_row: usize, rows: &mut [egui::Color32]| {
+        for (i, pixel) in rows.iter_mut().enumerate() {
+            let (x, y) = (i % width, first_row + i / width);
+            // Fragments are shaded at their centers.
+            let frag_coord = vec4(
+                origin.x + x as f32 + 0.5,
+                origin.y + y as f32 + 0.5,
+                0.0,
+                1.0,
+            );
+            let mut output = Vec4::ZERO;
+            shader(frag_coord, constants, reference_orbit, camera, &mut output);
+            *pixel = to_color32(output);
+        }
+    };
+    // Spawning threads panics on the web.
+    #[cfg(target_arch = "wasm32")]
+    render_rows(0, &mut image.pixels);
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+        let rows_per_thread = (height + threads - 1) / threads;
+        std::thread::scope(|scope| {
+            for (chunk_index, chunk) in image.pixels.chunks_mut(rows_per_thread * width).enumerate()
+            {
+                scope.spawn(move || render_rows(chunk_index * rows_per_thread, chunk));
+            }
+        });
+    }
+    image
+}
+
+/// Converts a shader output to what a (non-sRGB) `Unorm` render target would store.
+fn to_color32(color: Vec4) -> egui::Color32 {
+    let [r, g, b, a] = (color.clamp(Vec4::ZERO, Vec4::ONE) * 255.0 + 0.5)
+        .to_array()
+        .map(|channel| channel as u8);
+    egui::Color32::from_rgba_unmultiplied(r, g, b, a)
+}
+
+/// Shows the output of a [`FragmentShader`] through egui textures, one per painted area.
+pub struct CpuRenderer {
+    shader: FragmentShader,
+    textures: HashMap<&'static str, egui::TextureHandle>,
+}
+
+impl CpuRenderer {
+    pub fn new(shader: FragmentShader) -> Self {
+        Self {
+            shader,
+            textures: HashMap::new(),
+        }
+    }
+
+    /// Renders into the texture called `name` and paints it over `rect`.
+    pub fn paint(
+        &mut self,
+        ui: &egui::Ui,
+        name: &'static str,
+        rect: egui::Rect,
+        shader_constants: &ShaderConstants,
+        reference_orbit: &[[f32; 2]],
+        camera: &Camera,
+    ) {
+        // Render at the physical resolution, and with the same `frag_coord`s as the GPU would.
+        let pixels_per_point = ui.ctx().pixels_per_point();
+        let origin = rect.min.to_vec2() * pixels_per_point;
+        let size = rect.size() * pixels_per_point;
+        let image = render(
+            self.shader,
+            shader_constants,
+            reference_orbit,
+            camera,
+            Vec2::new(origin.x, origin.y),
+            [size.x.round() as usize, size.y.round() as usize],
+        );
+
+        let options = egui::TextureOptions::NEAREST;
+        let texture = match self.textures.entry(name) {
+            Entry::Occupied(entry) => {
+                let texture = entry.into_mut();
+                texture.set(image, options);
+                texture
+            }
+            Entry::Vacant(entry) => entry.insert(ui.ctx().load_texture(name, image, options)),
+        };
+        let uv = egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0));
+        ui.painter()
+            .image(texture.id(), rect, uv, egui::Color32::WHITE);
+    }
+}
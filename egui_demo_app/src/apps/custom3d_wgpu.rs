@@ -1,227 +1,409 @@
-use std::{num::NonZeroU64, sync::Arc};
+use std::{
+    collections::HashMap,
+    sync::{
+        mpsc::{self, Receiver},
+        Arc,
+    },
+};
 
 use eframe::egui_wgpu::{self, wgpu};
 
-use shared::ShaderConstants;
-use std::borrow::Cow;
+use shared::{camera::Camera, palette, ShaderConstants};
+use spirv_std::glam::DVec2;
 
-struct CompiledShaderModules {
-    named_spv_modules: Vec<(Option<String>, wgpu::ShaderModuleDescriptorSpirV<'static>)>,
+use super::camera_controller::{CameraController, CameraMode};
+use super::cpu_renderer::{self, CpuRenderer};
+use super::perturbation::{ReferenceOrbit, DEEP_ZOOM_THRESHOLD};
+use super::shader_builder::{
+    maybe_watch, pick_entry_point, CompiledShaderModules, ShaderBuildError, ShaderBuildOptions,
+    ShaderBuildResult, ShaderWatcher,
+};
+use super::shader_reflection::EntryPoint;
+use super::{shader_file, shader_registry};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum IterationMode {
+    /// Always use `IterationSettings::base`.
+    Fixed,
+
+    /// Start from `IterationSettings::base` and add more the deeper the view is zoomed in.
+    ScaleWithZoom,
+
+    /// Oscillate between 10 and 30 over time.
+    Animated,
 }
 
-impl CompiledShaderModules {
-    fn spv_module_for_entry_point<'a>(
-        &'a self,
-        wanted_entry: &str,
-    ) -> wgpu::ShaderModuleDescriptorSpirV<'a> {
-        for (name, spv_module) in &self.named_spv_modules {
-            match name {
-                Some(name) if name != wanted_entry => continue,
-                _ => {
-                    return wgpu::ShaderModuleDescriptorSpirV {
-                        label: name.as_deref(),
-                        source: Cow::Borrowed(&spv_module.source),
-                    };
-                }
-            }
+/// How `ShaderConstants::max_iterations` is chosen.
+struct IterationSettings {
+    mode: IterationMode,
+    base: u32,
+
+    /// Extra iterations for every halving of `ShaderConstants::zoom` in `ScaleWithZoom` mode.
+    per_octave: u32,
+}
+
+impl Default for IterationSettings {
+    fn default() -> Self {
+        Self {
+            mode: IterationMode::Fixed,
+            base: 100,
+            per_octave: 25,
         }
-        unreachable!(
-            "{wanted_entry:?} not found in modules {:?}",
-            self.named_spv_modules
-                .iter()
-                .map(|(name, _)| name)
-                .collect::<Vec<_>>()
-        );
     }
 }
 
-fn maybe_watch(
-    // options: &Options,
-    #[cfg(not(any(target_os = "android", target_arch = "wasm32")))] on_watch: Option<
-        Box<dyn FnMut(CompiledShaderModules) + Send + 'static>,
-    >,
-) -> CompiledShaderModules {
-    #[cfg(not(any(target_os = "android", target_arch = "wasm32")))]
-    {
-        use spirv_builder::{CompileResult, MetadataPrintout, SpirvBuilder};
-        use std::path::PathBuf;
-        // Hack: spirv_builder builds into a custom directory if running under cargo, to not
-        // deadlock, and the default target directory if not. However, packages like `proc-macro2`
-        // have different configurations when being built here vs. when building
-        // rustc_codegen_spirv normally, so we *want* to build into a separate target directory, to
-        // not have to rebuild half the crate graph every time we run. So, pretend we're running
-        // under cargo by setting these environment variables.
-        std::env::set_var("OUT_DIR", env!("OUT_DIR"));
-        std::env::set_var("PROFILE", env!("PROFILE"));
-        let crate_name = "mandelbrot";
-        let manifest_dir = env!("CARGO_MANIFEST_DIR");
-        let crate_path = [manifest_dir, "..", "shaders", crate_name]
-            .iter()
-            .copied()
-            .collect::<PathBuf>();
-
-        // let has_debug_printf = options.force_spirv_passthru;
-        let has_debug_printf = false;
-
-        let builder = SpirvBuilder::new(crate_path, "spirv-unknown-vulkan1.1")
-            .print_metadata(MetadataPrintout::None)
-            .shader_panic_strategy(if has_debug_printf {
-                spirv_builder::ShaderPanicStrategy::DebugPrintfThenExit {
-                    print_inputs: true,
-                    print_backtrace: true,
-                }
-            } else {
-                spirv_builder::ShaderPanicStrategy::SilentExit
-            })
-            // HACK(eddyb) needed because of `debugPrintf` instrumentation limitations
-            // (see https://github.com/KhronosGroup/SPIRV-Tools/issues/4892).
-            .multimodule(has_debug_printf);
-        let initial_result = if let Some(mut f) = on_watch {
-            builder
-                .watch(move |compile_result| f(handle_compile_result(compile_result)))
-                .expect("Configuration is correct for watching")
-        } else {
-            builder.build().unwrap()
-        };
-        fn handle_compile_result(compile_result: CompileResult) -> CompiledShaderModules {
-            let load_spv_module = |path| {
-                let data = std::fs::read(path).unwrap();
-                // FIXME(eddyb) this reallocates all the data pointlessly, there is
-                // not a good reason to use `ShaderModuleDescriptorSpirV` specifically.
-                let spirv = Cow::Owned(wgpu::util::make_spirv_raw(&data).into_owned());
-                wgpu::ShaderModuleDescriptorSpirV {
-                    label: None,
-                    source: spirv,
-                }
-            };
-            CompiledShaderModules {
-                named_spv_modules: match compile_result.module {
-                    spirv_builder::ModuleResult::SingleModule(path) => {
-                        vec![(None, load_spv_module(path))]
-                    }
-                    spirv_builder::ModuleResult::MultiModule(modules) => modules
-                        .into_iter()
-                        .map(|(name, path)| (Some(name), load_spv_module(path)))
-                        .collect(),
-                },
+impl IterationSettings {
+    fn max_iterations(&self, zoom: f32, time: f32) -> u32 {
+        match self.mode {
+            IterationMode::Fixed => self.base,
+            IterationMode::ScaleWithZoom => {
+                let octaves = (-zoom.log2()).max(0.0);
+                self.base + (octaves * self.per_octave as f32) as u32
             }
+            IterationMode::Animated => ((2.0 + (time * 3.0).cos()) * 10.0) as u32,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum FractalView {
+    Mandelbrot,
+
+    /// The Julia set for `JuliaSettings::c`, pannable and zoomable like the Mandelbrot view.
+    Julia,
+
+    /// The Mandelbrot set on the left, the Julia set for the point under the cursor on the right.
+    Split,
+}
+
+struct JuliaSettings {
+    view: FractalView,
+
+    /// The constant of the Julia set, as a point on the complex plane.
+    c: egui::Vec2,
+
+    /// Whether `c` stays put instead of following the cursor over the Mandelbrot view.
+    pinned: bool,
+}
+
+impl Default for JuliaSettings {
+    fn default() -> Self {
+        Self {
+            view: FractalView::Mandelbrot,
+            c: egui::vec2(-0.8, 0.156),
+            pinned: false,
         }
-        handle_compile_result(initial_result)
     }
 }
 
+/// Pan and zoom, kept in f64 so that they stay accurate far beyond what the f32
+/// `ShaderConstants` can resolve. Those are derived from this every frame.
+#[derive(Clone, Copy)]
+struct View {
+    translate: DVec2,
+    zoom: f64,
+}
+
+impl Default for View {
+    fn default() -> Self {
+        Self {
+            translate: DVec2::ZERO,
+            zoom: 1.0,
+        }
+    }
+}
+
+/// What the shaders run on.
+enum Backend {
+    Wgpu(egui_wgpu::RenderState),
+
+    /// Used when there is no wgpu adapter, see [`cpu_renderer`].
+    Cpu(CpuRenderer),
+}
+
 pub struct Custom3d {
+    /// The shader crate this app renders, or the name of the file it was loaded from.
+    crate_name: String,
+
+    backend: Backend,
+    shader_modules_receiver: Receiver<ShaderBuildResult>,
+
+    /// How the shader crate is built, and how to rebuild it differently. Absent for shaders
+    /// that aren't built by the app.
+    shader_build: Option<(ShaderBuildOptions, ShaderWatcher)>,
+
+    /// Whether a build started by the app (rather than by editing the shaders) is still running,
+    /// shown over whatever is drawn in the meantime.
+    compiling: bool,
+
+    /// Why the most recent shader build could not be used, if it failed.
+    /// The last good pipeline (if any) keeps rendering in the meantime.
+    shader_build_error: Option<ShaderBuildError>,
+
+    /// The most recently built modules that could be reflected, and their entry points.
+    shader_modules: Option<CompiledShaderModules>,
+    entry_points: Vec<EntryPoint>,
+
+    /// The fragment entry point picked to run, see [`selectable_fragment_entry_points`].
+    fragment_entry_point: Option<String>,
     shader_constants: ShaderConstants,
+    view: View,
+    zoom_settings: ZoomSettings,
+    iteration_settings: IterationSettings,
+    julia_settings: JuliaSettings,
+
+    /// Whether to switch to perturbation rendering past [`DEEP_ZOOM_THRESHOLD`].
+    deep_zoom: bool,
+    reference_orbit: ReferenceOrbit,
+
+    /// Whether dragging and scrolling move `camera` instead of panning and zooming `view`.
+    navigate_camera: bool,
+    camera: CameraController,
     start: std::time::Instant,
 }
 
+/// How the view reacts to the mouse wheel, trackpad and pinch gestures.
+struct ZoomSettings {
+    /// Exponential zoom rate per point scrolled.
+    speed: f32,
+
+    /// Smallest allowed `ShaderConstants::zoom`, i.e. how far in the view may go.
+    min: f64,
+
+    /// Largest allowed `ShaderConstants::zoom`, i.e. how far out the view may go.
+    max: f64,
+}
+
+impl Default for ZoomSettings {
+    fn default() -> Self {
+        Self {
+            speed: 0.002,
+            // Beyond this even the f64 reference orbit of the deep zoom runs out of precision.
+            min: 1e-13,
+            max: 4.0,
+        }
+    }
+}
+
 impl Custom3d {
-    pub fn new<'a>(cc: &'a eframe::CreationContext<'a>) -> Option<Self> {
+    /// Renders the shader crate `crate_name`, building it with debugPrintf if `debug_printf` is
+    /// set (see [`ShaderBuildOptions::debug_printf`]).
+    pub fn new<'a>(
+        cc: &'a eframe::CreationContext<'a>,
+        crate_name: &str,
+        debug_printf: bool,
+    ) -> Option<Self> {
         // Get the WGPU render state from the eframe creation context. This can also be retrieved
         // from `eframe::Frame` when you don't have a `CreationContext` available.
-        let wgpu_render_state = cc.wgpu_render_state.as_ref()?;
+        let Some(wgpu_render_state) = cc.wgpu_render_state.as_ref() else {
+            // Without a GPU there is nothing to compile the shaders for, evaluate the host build
+            // of the shader crate instead.
+            let Some(fragment_shader) = cpu_renderer::fragment_shader(crate_name) else {
+                tracing::warn!("No wgpu adapter and no CPU build of {crate_name}, skipping it");
+                return None;
+            };
+            tracing::info!("No wgpu adapter, rendering {crate_name} on the CPU");
+            let (_, shader_modules_receiver) = mpsc::channel();
+            return Some(Self::with_backend(
+                crate_name,
+                Backend::Cpu(CpuRenderer::new(fragment_shader)),
+                shader_modules_receiver,
+            ));
+        };
 
         let device = &wgpu_render_state.device;
-
-        let create_shader = |module: wgpu::ShaderModuleDescriptorSpirV| {
-            device.create_shader_module(wgpu::ShaderModuleDescriptor {
-                label: Some("custom3d"),
-                source: wgpu::ShaderSource::SpirV(module.source).into(),
-            })
+        let push_constants = uses_push_constants(device);
+        if !device.features().contains(wgpu::Features::PUSH_CONSTANTS) {
+            tracing::info!(
+                "No push constant support, {crate_name} reads its constants from a uniform buffer"
+            );
+        } else if !push_constants {
+            tracing::warn!(
+                "ShaderConstants take {} bytes, more than the {} bytes of push constants the \
+                 device supports, {crate_name} reads them from a uniform buffer instead",
+                std::mem::size_of::<ShaderConstants>(),
+                device.limits().max_push_constant_size,
+            );
+        }
+        let debug_printf = debug_printf && supports_debug_printf(device);
+        let options = ShaderBuildOptions {
+            // Modules with `main_fs` declare push constants, which the device would reject.
+            multimodule: !push_constants,
+            debug_printf,
         };
-        let compiled_shader_modules = maybe_watch(
-            // options,
-            #[cfg(not(any(target_os = "android", target_arch = "wasm32")))]
-            {
-                // let proxy = event_loop.create_proxy();
-                // Some(Box::new(move |res| match proxy.send_event(res) {
-                //     Ok(it) => it,
-                //     // ShaderModuleDescriptor is not `Debug`, so can't use unwrap/expect
-                //     Err(_err) => panic!("Event loop dead"),
-                // }))
-                Some(Box::new(move |_| ()))
-            },
-        );
-        let vs_module_descr = compiled_shader_modules.spv_module_for_entry_point("main_vs");
-        let fs_module_descr = compiled_shader_modules.spv_module_for_entry_point("main_fs");
-        let vs_module = &create_shader(vs_module_descr);
-        let fs_module = &create_shader(fs_module_descr);
 
-        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            label: Some("custom3d"),
-            entries: &[wgpu::BindGroupLayoutEntry {
-                binding: 0,
-                visibility: wgpu::ShaderStages::VERTEX,
-                ty: wgpu::BindingType::Buffer {
-                    ty: wgpu::BufferBindingType::Uniform,
-                    has_dynamic_offset: false,
-                    min_binding_size: NonZeroU64::new(16),
-                },
-                count: None,
-            }],
+        // Shaders are compiled (and recompiled) on a background thread, but the pipeline has to
+        // be swapped from the UI thread, so forward them over a channel and wake up the UI.
+        let (shader_modules_sender, shader_modules_receiver) = mpsc::channel();
+        let ctx = cc.egui_ctx.clone();
+        let shader_watcher = maybe_watch(options, crate_name, move |shader_build_result| {
+            if shader_modules_sender.send(shader_build_result).is_ok() {
+                ctx.request_repaint();
+            }
         });
 
-        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-            label: Some("custom3d"),
-            bind_group_layouts: &[&bind_group_layout],
-            push_constant_ranges: &[wgpu::PushConstantRange {
-                stages: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
-                range: 0..std::mem::size_of::<ShaderConstants>() as u32,
-            }],
-        });
+        let mut slf = Self::with_backend(
+            crate_name,
+            Backend::Wgpu(wgpu_render_state.clone()),
+            shader_modules_receiver,
+        );
+        // Shaders embedded in the build can't be rebuilt with other options.
+        if cfg!(not(any(target_os = "android", target_arch = "wasm32"))) {
+            slf.shader_build = Some((options, shader_watcher));
+        }
+        slf.compiling = true;
+        Some(slf)
+    }
 
-        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("custom3d"),
-            layout: Some(&pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: vs_module,
-                entry_point: "main_vs",
-                buffers: &[],
-            },
-            fragment: Some(wgpu::FragmentState {
-                module: fs_module,
-                entry_point: "main_fs",
-                targets: &[Some(wgpu_render_state.target_format.into())],
-            }),
-            primitive: wgpu::PrimitiveState::default(),
-            depth_stencil: None,
-            multisample: wgpu::MultisampleState::default(),
-            multiview: None,
-        });
+    /// Renders the prebuilt shader in `bytes` (see [`shader_file`]) instead of a shader crate.
+    /// `name` should be unique among the apps, like the name of the file.
+    pub fn from_shader_file(
+        render_state: &egui_wgpu::RenderState,
+        name: &str,
+        bytes: &[u8],
+    ) -> Self {
+        // Files are loaded once, so nothing is ever sent.
+        let (_, shader_modules_receiver) = mpsc::channel();
+        let mut slf = Self::with_backend(
+            name,
+            Backend::Wgpu(render_state.clone()),
+            shader_modules_receiver,
+        );
+        slf.set_shaders(shader_file::load(bytes));
+        slf
+    }
 
-        // Because the graphics pipeline must have the same lifetime as the egui render pass,
-        // instead of storing the pipeline in our `Custom3D` struct, we insert it into the
-        // `paint_callback_resources` type map, which is stored alongside the render pass.
-        wgpu_render_state
-            .renderer
-            .write()
-            .paint_callback_resources
-            .insert(TriangleRenderResources { pipeline });
-
-        Some(Self {
-            shader_constants: ShaderConstants {
-                width: 400,
-                height: 400,
-                time: 0.0,
-                cursor_x: 0.0,
-                cursor_y: 0.0,
-                drag_start_x: 0.0,
-                drag_start_y: 0.0,
-                drag_end_x: 0.0,
-                drag_end_y: 0.0,
-                zoom: 1.0,
-                translate_x: 0.0,
-                translate_y: 0.0,
-                mouse_button_pressed: 0,
-                mouse_button_press_time: [0.0, 0.0, 0.0],
-            },
+    fn with_backend(
+        crate_name: &str,
+        backend: Backend,
+        shader_modules_receiver: Receiver<ShaderBuildResult>,
+    ) -> Self {
+        Self {
+            crate_name: crate_name.to_owned(),
+            backend,
+            shader_modules_receiver,
+            shader_build: None,
+            compiling: false,
+            shader_build_error: None,
+            shader_modules: None,
+            entry_points: Vec::new(),
+            fragment_entry_point: None,
+            // The size and the iteration limit are set every frame.
+            shader_constants: ShaderConstants::new(400, 400),
+            view: Default::default(),
+            zoom_settings: Default::default(),
+            iteration_settings: Default::default(),
+            julia_settings: Default::default(),
+            deep_zoom: true,
+            reference_orbit: Default::default(),
+            navigate_camera: shader_registry::uses_camera(crate_name),
+            camera: Default::default(),
             start: std::time::Instant::now(),
-        })
+        }
+    }
+
+    /// Rebuilds the pipeline from the most recently recompiled shaders, if any.
+    fn reload_shaders(&mut self) {
+        if let Some(shader_build_result) = self.shader_modules_receiver.try_iter().last() {
+            self.set_shaders(shader_build_result);
+        }
+    }
+
+    fn set_shaders(&mut self, shader_build_result: ShaderBuildResult) {
+        self.compiling = false;
+        let reflected = shader_build_result.and_then(|compiled_shader_modules| {
+            Ok((
+                compiled_shader_modules.entry_points()?,
+                compiled_shader_modules,
+            ))
+        });
+        match reflected {
+            Ok((entry_points, compiled_shader_modules)) => {
+                // Keep running the picked entry point if it's still there.
+                let selectable =
+                    selectable_fragment_entry_points(&entry_points).collect::<Vec<_>>();
+                if !self
+                    .fragment_entry_point
+                    .as_deref()
+                    .is_some_and(|name| selectable.contains(&name))
+                {
+                    self.fragment_entry_point = selectable
+                        .iter()
+                        .find(|&&name| name == "main_fs")
+                        .or(selectable.first())
+                        .map(|&name| name.to_owned());
+                }
+                self.entry_points = entry_points;
+                self.shader_modules = Some(compiled_shader_modules);
+                self.create_pipeline();
+            }
+            Err(err) => {
+                tracing::error!("{err}");
+                self.shader_build_error = Some(err);
+            }
+        }
+    }
+
+    /// Creates the pipeline running [`Self::fragment_entry_point`] of the current modules.
+    fn create_pipeline(&mut self) {
+        let (Backend::Wgpu(render_state), Some(compiled_shader_modules)) =
+            (&self.backend, &self.shader_modules)
+        else {
+            return;
+        };
+        let resources = TriangleRenderResources::new(
+            render_state,
+            compiled_shader_modules,
+            &self.entry_points,
+            self.fragment_entry_point.as_deref(),
+            self.can_deep_zoom(),
+        );
+        match resources {
+            Ok(resources) => {
+                // Because the graphics pipeline must have the same lifetime as the egui render
+                // pass, instead of storing the pipeline in our `Custom3D` struct, we insert it
+                // into the `paint_callback_resources` type map, which is stored alongside the
+                // render pass. Every shader crate has its own entry, keyed by crate name.
+                render_state
+                    .renderer
+                    .write()
+                    .paint_callback_resources
+                    .entry::<HashMap<String, TriangleRenderResources>>()
+                    .or_insert_with(HashMap::new)
+                    .insert(self.crate_name.clone(), resources);
+                self.shader_build_error = None;
+            }
+            Err(err) => {
+                tracing::error!("{err}");
+                self.shader_build_error = Some(err);
+            }
+        }
     }
 }
 
 impl eframe::App for Custom3d {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        self.reload_shaders();
+
+        if let Some(err) = &self.shader_build_error {
+            egui::TopBottomPanel::bottom("shader_build_error")
+                .resizable(true)
+                .show(ctx, |ui| {
+                    ui.colored_label(ui.visuals().error_fg_color, err.to_string());
+                    if let Some(diagnostics) = err.diagnostics() {
+                        egui::ScrollArea::vertical()
+                            .auto_shrink([false, true])
+                            .show(ui, |ui| {
+                                ui.label(egui::RichText::new(diagnostics).monospace());
+                            });
+                    }
+                });
+        }
+
+        egui::SidePanel::right("custom3d_controls").show(ctx, |ui| {
+            self.controls_ui(ui);
+        });
+
         egui::CentralPanel::default().show(ctx, |ui| {
             egui::ScrollArea::both()
                 .auto_shrink([false; 2])
@@ -235,18 +417,500 @@ impl eframe::App for Custom3d {
 }
 
 impl Custom3d {
+    fn controls_ui(&mut self, ui: &mut egui::Ui) {
+        if matches!(self.backend, Backend::Wgpu(_)) {
+            self.shader_ui(ui);
+            ui.separator();
+        }
+
+        if shader_registry::is_fractal(&self.crate_name) {
+            self.fractal_ui(ui);
+            ui.separator();
+        }
+
+        self.camera_ui(ui);
+
+        ui.separator();
+
+        ui.heading("Zoom");
+        let zoom_settings = &mut self.zoom_settings;
+        ui.add(
+            egui::Slider::new(&mut zoom_settings.speed, 0.0001..=0.01)
+                .logarithmic(true)
+                .text("speed"),
+        );
+        ui.add(
+            egui::Slider::new(&mut zoom_settings.min, 1e-13..=1.0)
+                .logarithmic(true)
+                .text("min"),
+        );
+        ui.add(
+            egui::Slider::new(&mut zoom_settings.max, 1.0..=100.0)
+                .logarithmic(true)
+                .text("max"),
+        );
+        if shader_registry::is_fractal(&self.crate_name) {
+            ui.add_enabled(
+                self.can_deep_zoom(),
+                egui::Checkbox::new(&mut self.deep_zoom, "Deep zoom"),
+            )
+            .on_hover_text("Use perturbation theory to zoom in beyond the limits of f32")
+            .on_disabled_hover_text(
+                "Needs a device with push constants and storage buffers, \
+                 the shaders iterate in plain f32 without them",
+            );
+        }
+        ui.label(format!("zoom: {:e}", self.view.zoom));
+        if ui.button("Reset view").clicked() {
+            let constants = &self.shader_constants;
+            self.view = View {
+                // Cancel out the offset of the most recent drag.
+                translate: DVec2::new(
+                    (constants.drag_end_x - constants.drag_start_x) as f64,
+                    (constants.drag_end_y - constants.drag_start_y) as f64,
+                ),
+                ..Default::default()
+            };
+        }
+    }
+
+    fn shader_ui(&mut self, ui: &mut egui::Ui) {
+        ui.heading("Shader");
+        if let (Backend::Wgpu(render_state), Some((options, shader_watcher))) =
+            (&self.backend, &mut self.shader_build)
+        {
+            let device = &render_state.device;
+            let mut new_options = *options;
+            ui.add_enabled(
+                supports_debug_printf(device),
+                egui::Checkbox::new(&mut new_options.debug_printf, "debugPrintf"),
+            )
+            .on_hover_text(
+                "Report shader panics and debug_printfln! output in the log. Needs the Vulkan \
+                 validation layers, start the app with --debug-printf to enable them.",
+            )
+            .on_disabled_hover_text("Needs a Vulkan device supporting SPIR-V passthrough");
+            // Without push constants, or with debugPrintf, there have to be multiple modules.
+            let mut multimodule = new_options.multimodule || new_options.debug_printf;
+            ui.add_enabled(
+                uses_push_constants(device) && !new_options.debug_printf,
+                egui::Checkbox::new(&mut multimodule, "Module per entry point"),
+            );
+            if !new_options.debug_printf {
+                new_options.multimodule = multimodule;
+            }
+            if new_options != *options {
+                *options = new_options;
+                shader_watcher.rebuild(new_options);
+                self.compiling = true;
+            }
+        }
+
+        if self.entry_points.is_empty() {
+            return;
+        }
+        let mut fragment_entry_point = self.fragment_entry_point.clone();
+        egui::ComboBox::from_label("fragment entry point")
+            .selected_text(fragment_entry_point.as_deref().unwrap_or_default())
+            .show_ui(ui, |ui| {
+                for name in selectable_fragment_entry_points(&self.entry_points) {
+                    ui.selectable_value(&mut fragment_entry_point, Some(name.to_owned()), name);
+                }
+            });
+        if fragment_entry_point != self.fragment_entry_point {
+            self.fragment_entry_point = fragment_entry_point;
+            self.create_pipeline();
+        }
+        ui.collapsing("Entry points", |ui| {
+            for entry_point in &self.entry_points {
+                let title = format!("{:?} {}", entry_point.stage, entry_point.name);
+                ui.collapsing(title, |ui| {
+                    let lines = |ui: &mut egui::Ui, label: &str, lines: &[String]| {
+                        if !lines.is_empty() {
+                            ui.label(label);
+                            ui.indent(label, |ui| {
+                                for line in lines {
+                                    ui.label(egui::RichText::new(line).monospace());
+                                }
+                            });
+                        }
+                    };
+                    lines(ui, "Inputs", &entry_point.inputs);
+                    lines(ui, "Outputs", &entry_point.outputs);
+                    lines(ui, "Resources", &entry_point.resources);
+                    if let Some([x, y, z]) = entry_point.workgroup_size {
+                        ui.label(format!("Workgroup size: {x}×{y}×{z}"));
+                    }
+                });
+            }
+        });
+    }
+
+    /// The controls of the constants only [`shader_registry::is_fractal`] crates read.
+    fn fractal_ui(&mut self, ui: &mut egui::Ui) {
+        const PALETTES: [(&str, u32); palette::COUNT as usize] = [
+            ("Rainbow", palette::RAINBOW),
+            ("Sunset", palette::SUNSET),
+            ("Ocean", palette::OCEAN),
+            ("Candy", palette::CANDY),
+            ("Ultra Fractal", palette::ULTRA_FRACTAL),
+        ];
+        ui.heading("Colors");
+        let selected = &mut self.shader_constants.palette;
+        let selected_name = PALETTES
+            .iter()
+            .find(|(_, palette)| palette == selected)
+            .map_or("", |(name, _)| name);
+        egui::ComboBox::from_label("palette")
+            .selected_text(selected_name)
+            .show_ui(ui, |ui| {
+                for (name, palette) in PALETTES {
+                    ui.selectable_value(selected, palette, name);
+                }
+            });
+
+        ui.separator();
+
+        ui.heading("Iterations");
+        let iteration_settings = &mut self.iteration_settings;
+        ui.horizontal(|ui| {
+            ui.selectable_value(&mut iteration_settings.mode, IterationMode::Fixed, "Fixed");
+            ui.selectable_value(
+                &mut iteration_settings.mode,
+                IterationMode::ScaleWithZoom,
+                "Scale with zoom",
+            );
+            ui.selectable_value(
+                &mut iteration_settings.mode,
+                IterationMode::Animated,
+                "Animated",
+            );
+        });
+        ui.add_enabled(
+            iteration_settings.mode != IterationMode::Animated,
+            egui::Slider::new(&mut iteration_settings.base, 1..=10_000)
+                .logarithmic(true)
+                .text("iterations"),
+        );
+        ui.add_enabled(
+            iteration_settings.mode == IterationMode::ScaleWithZoom,
+            egui::Slider::new(&mut iteration_settings.per_octave, 0..=200).text("per octave"),
+        );
+        ui.label(format!(
+            "max iterations: {}",
+            self.shader_constants.max_iterations
+        ));
+
+        ui.separator();
+
+        ui.heading("Julia set");
+        let julia_settings = &mut self.julia_settings;
+        ui.horizontal(|ui| {
+            ui.selectable_value(
+                &mut julia_settings.view,
+                FractalView::Mandelbrot,
+                "Mandelbrot",
+            );
+            ui.selectable_value(&mut julia_settings.view, FractalView::Julia, "Julia");
+            ui.selectable_value(&mut julia_settings.view, FractalView::Split, "Split");
+        });
+        ui.horizontal(|ui| {
+            ui.label("c =");
+            ui.add(egui::DragValue::new(&mut julia_settings.c.x).speed(0.001));
+            ui.add(
+                egui::DragValue::new(&mut julia_settings.c.y)
+                    .speed(0.001)
+                    .suffix("i"),
+            );
+        });
+        ui.checkbox(&mut julia_settings.pinned, "Pin c")
+            .on_hover_text("Right click the Mandelbrot view to pin c to a point");
+    }
+
+    fn camera_ui(&mut self, ui: &mut egui::Ui) {
+        ui.heading("Camera");
+        ui.checkbox(&mut self.navigate_camera, "3D navigation")
+            .on_hover_text(
+                "Drag to look around, scroll to zoom, WASD to move and Q/E to go down/up",
+            );
+        let camera = &mut self.camera;
+        ui.add_enabled_ui(self.navigate_camera, |ui| {
+            ui.horizontal(|ui| {
+                ui.selectable_value(&mut camera.mode, CameraMode::Orbit, "Orbit");
+                ui.selectable_value(&mut camera.mode, CameraMode::Fly, "Fly");
+            });
+            ui.add(egui::Slider::new(&mut camera.fov_y, 10.0..=120.0).text("field of view"));
+            ui.add(
+                egui::Slider::new(&mut camera.speed, 0.1..=50.0)
+                    .logarithmic(true)
+                    .text("speed"),
+            );
+            if ui.button("Reset camera").clicked() {
+                *camera = Default::default();
+            }
+        });
+    }
+
+    /// Zooms by `factor` (> 1 zooms in) while keeping the point under `pivot` in place.
+    ///
+    /// `pivot` is in the same physical pixel coordinates as `frag_coord` in the shaders.
+    fn zoom_around(&mut self, pivot: egui::Pos2, factor: f32) {
+        let zoom =
+            (self.view.zoom / factor as f64).clamp(self.zoom_settings.min, self.zoom_settings.max);
+        // Shaders map `frag_coord` to
+        // `zoom * (frag_coord + translate + drag_start - drag_end - size / 2) / height`,
+        // so solve for the `translate` that maps `pivot` to the same point at the new zoom.
+        let pivot = DVec2::new(pivot.x as f64, pivot.y as f64);
+        let offset = self.plane_offset();
+        let scale = self.view.zoom / zoom;
+        self.view.translate += scale * (pivot + offset) - (pivot + offset);
+        self.view.zoom = zoom;
+    }
+
+    /// Fills in the cursor, drag and button fields of the shader constants.
+    ///
+    /// Positions are in physical pixels relative to the top left corner of `rect`.
+    fn update_mouse(&mut self, ui: &egui::Ui, rect: egui::Rect, response: &egui::Response) {
+        let pixels_per_point = ui.ctx().pixels_per_point();
+        let to_pixels = |pos: egui::Pos2| (pos - rect.min) * pixels_per_point;
+        let constants = &mut self.shader_constants;
+
+        if let Some(pos) = response.hover_pos().or(response.interact_pointer_pos()) {
+            let pos = to_pixels(pos);
+            constants.cursor_x = pos.x;
+            constants.cursor_y = pos.y;
+        }
+
+        // Shaders pan by `translate + drag_start - drag_end`, so the previous drag is only
+        // folded into `translate` once a new one starts, and stays visible until then.
+        if response.drag_started() {
+            self.view.translate += DVec2::new(
+                (constants.drag_start_x - constants.drag_end_x) as f64,
+                (constants.drag_start_y - constants.drag_end_y) as f64,
+            );
+            // The drag only registers once the pointer moved a bit, start it where it was pressed.
+            let press_origin = ui.input(|i| i.pointer.press_origin());
+            if let Some(pos) = press_origin.or(response.interact_pointer_pos()) {
+                let pos = to_pixels(pos);
+                constants.drag_start_x = pos.x;
+                constants.drag_start_y = pos.y;
+                constants.drag_end_x = pos.x;
+                constants.drag_end_y = pos.y;
+            }
+        }
+        if response.dragged() {
+            if let Some(pos) = response.interact_pointer_pos() {
+                let pos = to_pixels(pos);
+                constants.drag_end_x = pos.x;
+                constants.drag_end_y = pos.y;
+            }
+        }
+
+        let on_canvas = response.hovered() || response.is_pointer_button_down_on();
+        let buttons = [
+            egui::PointerButton::Primary,
+            egui::PointerButton::Middle,
+            egui::PointerButton::Secondary,
+        ];
+        constants.mouse_button_pressed = 0;
+        ui.input(|i| {
+            for (bit, button) in buttons.into_iter().enumerate() {
+                if on_canvas && i.pointer.button_down(button) {
+                    constants.mouse_button_pressed |= 1 << bit;
+                }
+                if response.hovered() && i.pointer.button_pressed(button) {
+                    constants.set_mouse_button_press_time(bit, constants.time);
+                }
+            }
+        });
+    }
+
+    /// What shaders add to `frag_coord` before scaling it by `zoom / height`.
+    fn plane_offset(&self) -> DVec2 {
+        let constants = &self.shader_constants;
+        self.view.translate
+            + DVec2::new(
+                (constants.drag_start_x - constants.drag_end_x) as f64,
+                (constants.drag_start_y - constants.drag_end_y) as f64,
+            )
+            - 0.5 * DVec2::new(constants.width as f64, constants.height as f64)
+    }
+
+    /// The point on the complex plane that shaders put at `pixel`, in the same physical pixel
+    /// coordinates as `frag_coord`.
+    fn plane_point(&self, pixel: egui::Pos2) -> DVec2 {
+        let pixel = DVec2::new(pixel.x as f64, pixel.y as f64);
+        self.view.zoom * (pixel + self.plane_offset()) / self.shader_constants.height as f64
+    }
+
+    /// Inverse of [`Self::plane_point`].
+    fn plane_point_to_pixel(&self, point: DVec2) -> egui::Pos2 {
+        let pixel =
+            point * self.shader_constants.height as f64 / self.view.zoom - self.plane_offset();
+        egui::pos2(pixel.x as f32, pixel.y as f32)
+    }
+
     fn custom_painting(&mut self, ui: &mut egui::Ui) {
         let available_size = ui.available_size();
-        let (rect, response) = ui.allocate_exact_size(available_size, egui::Sense::drag());
+        let (rect, _) = ui.allocate_exact_size(available_size, egui::Sense::hover());
+        let (rect, julia_rect) = if self.julia_settings.view == FractalView::Split {
+            let split_x = rect.center().x;
+            (
+                egui::Rect::from_min_max(rect.min, egui::pos2(split_x, rect.max.y)),
+                Some(egui::Rect::from_min_max(
+                    egui::pos2(split_x, rect.min.y),
+                    rect.max,
+                )),
+            )
+        } else {
+            (rect, None)
+        };
+        let response = ui.interact(rect, ui.id().with("custom3d_canvas"), egui::Sense::drag());
+        let pixels_per_point = ui.ctx().pixels_per_point();
 
-        self.shader_constants.width = available_size.x as u32;
-        self.shader_constants.height = available_size.y as u32;
-        self.shader_constants.translate_x -= response.drag_delta().x;
-        self.shader_constants.translate_y -= response.drag_delta().y;
+        self.shader_constants.width = rect.width() as u32;
+        self.shader_constants.height = rect.height() as u32;
         self.shader_constants.time = self.start.elapsed().as_secs_f32();
+        self.update_mouse(ui, rect, &response);
+        if let Some(hover_pos) = response.hover_pos() {
+            let (scroll_delta, zoom_delta) = ui.input(|i| (i.scroll_delta, i.zoom_delta()));
+            // Pinch gestures (and ctrl + scroll) arrive as `zoom_delta`, plain scrolling doesn't.
+            let factor = zoom_delta * (scroll_delta.y * self.zoom_settings.speed).exp();
+            if self.navigate_camera {
+                self.camera.zoom(factor);
+                ui.input(|i| self.camera.update_keys(i));
+            } else if factor != 1.0 {
+                self.zoom_around((hover_pos.to_vec2() * pixels_per_point).to_pos2(), factor);
+            }
+        }
+        if self.navigate_camera && response.dragged() {
+            self.camera.rotate(response.drag_delta() * pixels_per_point);
+        }
+        // Cameras render into the painted area in physical pixels, like `frag_coord`.
+        let camera_for = |camera: &CameraController, rect: egui::Rect| {
+            camera.camera(egui::Rect::from_min_size(
+                (rect.min.to_vec2() * pixels_per_point).to_pos2(),
+                rect.size() * pixels_per_point,
+            ))
+        };
+        self.shader_constants.zoom = self.view.zoom as f32;
+        self.shader_constants.translate_x = self.view.translate.x as f32;
+        self.shader_constants.translate_y = self.view.translate.y as f32;
+        self.shader_constants.max_iterations = self
+            .iteration_settings
+            .max_iterations(self.shader_constants.zoom, self.shader_constants.time);
+
+        let view = self.julia_settings.view;
+        if view == FractalView::Split {
+            if let Some(hover_pos) = response.hover_pos() {
+                let point = self.plane_point((hover_pos.to_vec2() * pixels_per_point).to_pos2());
+                let point = egui::vec2(point.x as f32, point.y as f32);
+                if response.secondary_clicked() {
+                    self.julia_settings.c = point;
+                    self.julia_settings.pinned = !self.julia_settings.pinned;
+                } else if !self.julia_settings.pinned {
+                    self.julia_settings.c = point;
+                }
+            }
+        }
+        let c = self.julia_settings.c;
+        self.shader_constants.julia = (view == FractalView::Julia) as u32;
+        self.shader_constants.julia_x = c.x;
+        self.shader_constants.julia_y = c.y;
+
+        let mut shader_constants = self.shader_constants;
+        let deep_zoom =
+            self.deep_zoom && self.view.zoom < DEEP_ZOOM_THRESHOLD && self.can_deep_zoom();
+        let reference_orbit = deep_zoom.then(|| {
+            // Iterate the center of the view precisely, and have the shader only iterate how
+            // each pixel differs from it, so make the shader's coordinates relative to the center.
+            let center = rect.center().to_vec2() * pixels_per_point;
+            let reference = self.plane_point(center.to_pos2());
+            shader_constants.translate_x = (0.5 * shader_constants.width as f32 - center.x)
+                - (shader_constants.drag_start_x - shader_constants.drag_end_x);
+            shader_constants.translate_y = (0.5 * shader_constants.height as f32 - center.y)
+                - (shader_constants.drag_start_y - shader_constants.drag_end_y);
+            let (z0, c) = if shader_constants.julia != 0 {
+                (reference, DVec2::new(c.x as f64, c.y as f64))
+            } else {
+                (DVec2::ZERO, reference)
+            };
+            let orbit = self
+                .reference_orbit
+                .get(z0, c, shader_constants.max_iterations);
+            shader_constants.reference_orbit_len = orbit.len() as u32;
+            orbit
+        });
+        let camera = camera_for(&self.camera, rect);
+        self.paint_shader(ui, "main", rect, shader_constants, reference_orbit, camera);
+        if self.compiling {
+            let spinner_rect = egui::Rect::from_center_size(rect.center(), egui::vec2(200.0, 60.0));
+            ui.allocate_ui_at_rect(spinner_rect, |ui| {
+                ui.vertical_centered(|ui| {
+                    ui.spinner();
+                    ui.label(format!("Compiling {}…", self.crate_name));
+                });
+            });
+        }
+
+        if let Some(julia_rect) = julia_rect {
+            // Show the whole Julia set centered in its half, whatever the Mandelbrot view does.
+            let center = julia_rect.center().to_vec2() * pixels_per_point;
+            let julia_constants = ShaderConstants {
+                width: julia_rect.width() as u32,
+                height: julia_rect.height() as u32,
+                zoom: 3.0,
+                translate_x: 0.5 * julia_rect.width() - center.x,
+                translate_y: 0.5 * julia_rect.height() - center.y,
+                drag_start_x: 0.0,
+                drag_start_y: 0.0,
+                drag_end_x: 0.0,
+                drag_end_y: 0.0,
+                julia: 1,
+                reference_orbit_len: 0,
+                ..self.shader_constants
+            };
+            let camera = camera_for(&self.camera, julia_rect);
+            self.paint_shader(ui, "julia", julia_rect, julia_constants, None, camera);
+
+            let c = DVec2::new(c.x as f64, c.y as f64);
+            let marker = self.plane_point_to_pixel(c).to_vec2() / pixels_per_point;
+            let marker = marker.to_pos2();
+            if rect.contains(marker) {
+                let stroke = egui::Stroke::new(1.5, egui::Color32::WHITE);
+                ui.painter().circle_stroke(marker, 4.0, stroke);
+            }
+        }
+    }
+
+    /// Whether the shader can render deep zooms, which only fractals do, given a reference orbit.
+    fn can_deep_zoom(&self) -> bool {
+        shader_registry::is_fractal(&self.crate_name)
+            && match &self.backend {
+                Backend::Wgpu(render_state) => supports_deep_zoom(&render_state.device),
+                Backend::Cpu(_) => true,
+            }
+    }
+
+    /// Paints the shader over `rect`. `name` tells apart the areas painted every frame.
+    fn paint_shader(
+        &mut self,
+        ui: &egui::Ui,
+        name: &'static str,
+        rect: egui::Rect,
+        shader_constants: ShaderConstants,
+        reference_orbit: Option<Arc<Vec<[f32; 2]>>>,
+        camera: Camera,
+    ) {
+        if let Backend::Cpu(cpu_renderer) = &mut self.backend {
+            let reference_orbit = reference_orbit.as_deref().map_or(&[][..], Vec::as_slice);
+            cpu_renderer.paint(ui, name, rect, &shader_constants, reference_orbit, &camera);
+            return;
+        }
 
         // Clone locals so we can move them into the paint callback:
-        let shader_constants = self.shader_constants;
+        let crate_name = self.crate_name.clone();
+        let prepare_crate_name = self.crate_name.clone();
 
         // The callback function for WGPU is in two stages: prepare, and paint.
         //
@@ -261,12 +925,28 @@ impl Custom3d {
         //
         // The paint callback is called after prepare and is given access to the render pass, which
         // can be used to issue draw commands.
-        let cb = egui_wgpu::CallbackFn::new().paint(
-            move |_info, render_pass, paint_callback_resources| {
-                let resources: &TriangleRenderResources = paint_callback_resources.get().unwrap();
-                resources.paint(render_pass, shader_constants);
-            },
-        );
+        let cb = egui_wgpu::CallbackFn::new()
+            .prepare(move |device, queue, _encoder, paint_callback_resources| {
+                if let Some(resources) = paint_callback_resources
+                    .get_mut::<HashMap<String, TriangleRenderResources>>()
+                    .and_then(|resources| resources.get_mut(&prepare_crate_name))
+                {
+                    if let Some(reference_orbit) = &reference_orbit {
+                        resources.upload_reference_orbit(device, queue, reference_orbit);
+                    }
+                    resources.prepare(device, queue, name, &shader_constants, &camera);
+                }
+                Vec::new()
+            })
+            .paint(move |_info, render_pass, paint_callback_resources| {
+                // There is nothing to draw until the shaders have built successfully once.
+                if let Some(resources) = paint_callback_resources
+                    .get::<HashMap<String, TriangleRenderResources>>()
+                    .and_then(|resources| resources.get(&crate_name))
+                {
+                    resources.paint(render_pass, name, shader_constants);
+                }
+            });
 
         let callback = egui::PaintCallback {
             rect,
@@ -277,22 +957,325 @@ impl Custom3d {
     }
 }
 
+/// Whether the device supports push constants large enough for [`ShaderConstants`], otherwise
+/// the shaders read them from a uniform buffer.
+fn uses_push_constants(device: &wgpu::Device) -> bool {
+    device.features().contains(wgpu::Features::PUSH_CONSTANTS)
+        && std::mem::size_of::<ShaderConstants>() <= device.limits().max_push_constant_size as usize
+}
+
+/// Whether the device can pass the reference orbit of deep zooms to the shaders. It is a
+/// storage buffer, which only `main_fs` reads: `main_fs_uniform` iterates in plain f32, as
+/// adapters without push constants (like WebGL) tend to lack storage buffers too.
+fn supports_deep_zoom(device: &wgpu::Device) -> bool {
+    uses_push_constants(device) && device.limits().max_storage_buffers_per_shader_stage > 0
+}
+
+/// Whether shaders built with [`ShaderBuildOptions::debug_printf`] work on the device.
+fn supports_debug_printf(device: &wgpu::Device) -> bool {
+    device
+        .features()
+        .contains(wgpu::Features::SPIRV_SHADER_PASSTHROUGH)
+}
+
+/// Fragment entry points reading [`ShaderConstants`] from a uniform buffer are named after
+/// the one they wrap, with this suffix, and take its place without push constants.
+const UNIFORM_SUFFIX: &str = "_uniform";
+
+/// The fragment entry points that can be picked to run, which leaves out those taking the place
+/// of another one, see [`UNIFORM_SUFFIX`].
+fn selectable_fragment_entry_points(entry_points: &[EntryPoint]) -> impl Iterator<Item = &str> {
+    let fragment_entry_points = entry_points
+        .iter()
+        .filter(|entry_point| entry_point.stage == naga::ShaderStage::Fragment)
+        .map(|entry_point| entry_point.name.as_str());
+    fragment_entry_points.clone().filter(move |name| {
+        !name
+            .strip_suffix(UNIFORM_SUFFIX)
+            .is_some_and(|wrapped| fragment_entry_points.clone().any(|name| name == wrapped))
+    })
+}
+
 struct TriangleRenderResources {
     pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+
+    /// Whether [`ShaderConstants`] are passed as push constants, rather than in
+    /// [`AreaResources::constants_buffer`].
+    push_constants: bool,
+
+    /// Storage buffer with the reference orbit used for deep zooms, bound at binding 0 for
+    /// fractals on devices that support them (see [`Custom3d::can_deep_zoom`]).
+    reference_orbit_buffer: Option<wgpu::Buffer>,
+
+    /// The buffers of every area painted with this pipeline, by the name given to
+    /// [`Custom3d::paint_shader`].
+    areas: HashMap<&'static str, AreaResources>,
+}
+
+/// The uniforms of one of the areas painted in a frame. Every area needs its own, as all the
+/// buffer writes of a frame happen before any of them is painted.
+struct AreaResources {
+    /// Uniform buffer with the [`ShaderConstants`], when push constants aren't supported.
+    constants_buffer: wgpu::Buffer,
+
+    /// Uniform buffer with the [`Camera`] of 3D shaders.
+    camera_buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
 }
 
 impl TriangleRenderResources {
+    /// Creates the pipeline running `fragment_entry_point` (or `main_fs`), binding a reference
+    /// orbit for deep zooms if `reference_orbit` is set.
+    fn new(
+        render_state: &egui_wgpu::RenderState,
+        compiled_shader_modules: &CompiledShaderModules,
+        entry_points: &[EntryPoint],
+        fragment_entry_point: Option<&str>,
+        reference_orbit: bool,
+    ) -> Result<Self, ShaderBuildError> {
+        let device = &render_state.device;
+        let push_constants = uses_push_constants(device);
+        let vs_entry_point = pick_entry_point(entry_points, naga::ShaderStage::Vertex, "main_vs")?;
+        let fragment_entry_point = fragment_entry_point.unwrap_or("main_fs");
+        let uniform_entry_point = format!("{fragment_entry_point}{UNIFORM_SUFFIX}");
+        let fs_entry_point = pick_entry_point(
+            entry_points,
+            naga::ShaderStage::Fragment,
+            if !push_constants
+                && entry_points
+                    .iter()
+                    .any(|entry_point| entry_point.name == uniform_entry_point)
+            {
+                &uniform_entry_point
+            } else {
+                fragment_entry_point
+            },
+        )?;
+
+        let vs_module_descr = compiled_shader_modules.module_for_entry_point(vs_entry_point)?;
+        let fs_module_descr = compiled_shader_modules.module_for_entry_point(fs_entry_point)?;
+
+        // Shaders loaded from files may not fit the pipeline below, catch that instead of
+        // letting wgpu panic.
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let vs_module = &vs_module_descr.create(device);
+        let fs_module = &fs_module_descr.create(device);
+
+        let uniform_entry = |binding| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let mut entries = vec![uniform_entry(1), uniform_entry(2)];
+        if reference_orbit {
+            entries.push(wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Storage { read_only: true },
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            });
+        }
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("custom3d"),
+            entries: &entries,
+        });
+
+        let push_constant_ranges: &[_] = if push_constants {
+            &[wgpu::PushConstantRange {
+                stages: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+                range: 0..std::mem::size_of::<ShaderConstants>() as u32,
+            }]
+        } else {
+            &[]
+        };
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("custom3d"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges,
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("custom3d"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: vs_module,
+                entry_point: vs_entry_point,
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: fs_module,
+                entry_point: fs_entry_point,
+                targets: &[Some(render_state.target_format.into())],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+        if let Some(error) = pollster::block_on(device.pop_error_scope()) {
+            return Err(ShaderBuildError::Pipeline {
+                error: error.to_string(),
+            });
+        }
+
+        // Storage buffers can't be empty, start with room for a single point.
+        let reference_orbit_buffer =
+            reference_orbit.then(|| Self::create_reference_orbit_buffer(device, 1));
+        Ok(Self {
+            pipeline,
+            bind_group_layout,
+            push_constants,
+            reference_orbit_buffer,
+            areas: HashMap::new(),
+        })
+    }
+
+    fn create_reference_orbit_buffer(device: &wgpu::Device, len: usize) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("custom3d reference orbit"),
+            size: (len * std::mem::size_of::<[f32; 2]>()) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+
+    fn create_uniform_buffer<T>(device: &wgpu::Device, label: &str) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(label),
+            // Uniform blocks are laid out in multiples of 16 bytes.
+            size: wgpu::util::align_to(std::mem::size_of::<T>(), 16) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+
+    fn create_bind_group(
+        device: &wgpu::Device,
+        bind_group_layout: &wgpu::BindGroupLayout,
+        reference_orbit_buffer: Option<&wgpu::Buffer>,
+        camera_buffer: &wgpu::Buffer,
+        constants_buffer: &wgpu::Buffer,
+    ) -> wgpu::BindGroup {
+        let mut entries = vec![
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: camera_buffer.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 2,
+                resource: constants_buffer.as_entire_binding(),
+            },
+        ];
+        if let Some(reference_orbit_buffer) = reference_orbit_buffer {
+            entries.push(wgpu::BindGroupEntry {
+                binding: 0,
+                resource: reference_orbit_buffer.as_entire_binding(),
+            });
+        }
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("custom3d"),
+            layout: bind_group_layout,
+            entries: &entries,
+        })
+    }
+
+    fn upload_reference_orbit(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        reference_orbit: &[[f32; 2]],
+    ) {
+        let Some(reference_orbit_buffer) = &mut self.reference_orbit_buffer else {
+            return;
+        };
+        let size = std::mem::size_of_val(reference_orbit) as wgpu::BufferAddress;
+        if size > reference_orbit_buffer.size() {
+            *reference_orbit_buffer = Self::create_reference_orbit_buffer(
+                device,
+                reference_orbit.len().next_power_of_two(),
+            );
+            for area in self.areas.values_mut() {
+                area.bind_group = Self::create_bind_group(
+                    device,
+                    &self.bind_group_layout,
+                    Some(reference_orbit_buffer),
+                    &area.camera_buffer,
+                    &area.constants_buffer,
+                );
+            }
+        }
+        queue.write_buffer(
+            reference_orbit_buffer,
+            0,
+            bytemuck::cast_slice(reference_orbit),
+        );
+    }
+
+    /// Uploads the uniforms of the area `name`, creating its buffers the first time.
+    fn prepare(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        name: &'static str,
+        shader_constants: &ShaderConstants,
+        camera: &Camera,
+    ) {
+        let area = self.areas.entry(name).or_insert_with(|| {
+            let camera_buffer = Self::create_uniform_buffer::<Camera>(device, "custom3d camera");
+            let constants_buffer =
+                Self::create_uniform_buffer::<ShaderConstants>(device, "custom3d constants");
+            let bind_group = Self::create_bind_group(
+                device,
+                &self.bind_group_layout,
+                self.reference_orbit_buffer.as_ref(),
+                &camera_buffer,
+                &constants_buffer,
+            );
+            AreaResources {
+                constants_buffer,
+                camera_buffer,
+                bind_group,
+            }
+        });
+        queue.write_buffer(&area.camera_buffer, 0, bytemuck::bytes_of(camera));
+        if !self.push_constants {
+            queue.write_buffer(
+                &area.constants_buffer,
+                0,
+                bytemuck::bytes_of(shader_constants),
+            );
+        }
+    }
+
     fn paint<'rp>(
         &'rp self,
         render_pass: &mut wgpu::RenderPass<'rp>,
+        name: &str,
         push_constants: ShaderConstants,
     ) {
+        // Areas are only known once they have been prepared.
+        let Some(area) = self.areas.get(name) else {
+            return;
+        };
         render_pass.set_pipeline(&self.pipeline);
-        render_pass.set_push_constants(
-            wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
-            0,
-            bytemuck::bytes_of(&push_constants),
-        );
+        render_pass.set_bind_group(0, &area.bind_group, &[]);
+        if self.push_constants {
+            render_pass.set_push_constants(
+                wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+                0,
+                bytemuck::bytes_of(&push_constants),
+            );
+        }
         render_pass.draw(0..3, 0..1);
     }
 }
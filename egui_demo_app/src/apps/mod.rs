@@ -1,3 +1,16 @@
+pub mod camera_controller;
+pub mod cpu_renderer;
 mod custom3d_wgpu;
+pub mod perturbation;
+mod shader_builder;
+#[cfg(not(any(target_os = "android", target_arch = "wasm32")))]
+mod shader_cache;
+#[cfg(not(any(target_os = "android", target_arch = "wasm32")))]
+mod shader_discovery;
+mod shader_file;
+mod shader_reflection;
+pub mod shader_registry;
 
 pub use custom3d_wgpu::Custom3d;
+#[cfg(not(any(target_os = "android", target_arch = "wasm32")))]
+pub use shader_builder::maybe_run_shader_build;
@@ -0,0 +1,56 @@
+//! Reference orbits for rendering escape-time fractals with perturbation theory.
+//!
+//! Past a zoom of about `1e-4` neighbouring pixels are no longer distinguishable in f32.
+//! Instead of iterating every pixel from scratch, a single reference point is iterated in f64
+//! on the CPU, and the shader only iterates each pixel's (tiny, hence precise in f32) difference
+//! from that reference orbit.
+
+use std::sync::Arc;
+
+use spirv_std::glam::DVec2;
+
+/// Below this `ShaderConstants::zoom` plain f32 iteration starts to break down.
+pub const DEEP_ZOOM_THRESHOLD: f64 = 1e-4;
+
+/// Must match the bailout radius used by the shaders, so that the reference orbit isn't cut
+/// short before the pixels around it escape.
+const BAILOUT: f64 = 256.0;
+
+fn square(z: DVec2) -> DVec2 {
+    DVec2::new(z.x * z.x - z.y * z.y, 2.0 * z.x * z.y)
+}
+
+/// Iterates `z = z² + c` from `z0`, returning every value of `z` (starting with `z0`) until it
+/// escapes or `max_iterations` is reached.
+pub fn reference_orbit(z0: DVec2, c: DVec2, max_iterations: u32) -> Vec<[f32; 2]> {
+    let mut orbit = Vec::with_capacity(max_iterations as usize + 1);
+    let mut z = z0;
+    orbit.push(z.as_vec2().to_array());
+    for _ in 0..max_iterations {
+        z = square(z) + c;
+        orbit.push(z.as_vec2().to_array());
+        if z.length_squared() > BAILOUT * BAILOUT {
+            break;
+        }
+    }
+    orbit
+}
+
+/// The reference orbit currently uploaded to the GPU, and what it was computed for.
+#[derive(Default)]
+pub struct ReferenceOrbit {
+    key: Option<(DVec2, DVec2, u32)>,
+    orbit: Arc<Vec<[f32; 2]>>,
+}
+
+impl ReferenceOrbit {
+    /// Returns the orbit for the given parameters, only recomputing it when they changed.
+    pub fn get(&mut self, z0: DVec2, c: DVec2, max_iterations: u32) -> Arc<Vec<[f32; 2]>> {
+        let key = Some((z0, c, max_iterations));
+        if self.key != key {
+            self.key = key;
+            self.orbit = Arc::new(reference_orbit(z0, c, max_iterations));
+        }
+        self.orbit.clone()
+    }
+}
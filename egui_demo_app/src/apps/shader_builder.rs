@@ -0,0 +1,596 @@
+use std::{borrow::Cow, fmt, path::PathBuf};
+
+use eframe::egui_wgpu::wgpu;
+
+use super::shader_reflection::{self, EntryPoint};
+
+/// The word every SPIR-V module starts with.
+const SPIRV_MAGIC: u32 = 0x0723_0203;
+
+/// Whether `bytes` look like a SPIR-V module, which `wgpu::util::make_spirv_raw` requires.
+pub fn is_spirv(bytes: &[u8]) -> bool {
+    bytes.len() % 4 == 0 && bytes.get(..4) == Some(&SPIRV_MAGIC.to_ne_bytes())
+}
+
+/// The code of a compiled module, in one of the languages wgpu accepts.
+pub enum ShaderCode {
+    SpirV(Cow<'static, [u32]>),
+    Wgsl(Cow<'static, str>),
+}
+
+impl ShaderCode {
+    fn source(&self) -> wgpu::ShaderSource<'_> {
+        match self {
+            Self::SpirV(words) => wgpu::ShaderSource::SpirV(Cow::Borrowed(words)),
+            Self::Wgsl(source) => wgpu::ShaderSource::Wgsl(Cow::Borrowed(source)),
+        }
+    }
+
+    fn parse(&self) -> Result<naga::Module, String> {
+        match self {
+            Self::SpirV(words) => naga::front::spv::parse_u8_slice(
+                bytemuck::cast_slice(words),
+                &naga::front::spv::Options::default(),
+            )
+            .map_err(|err| err.to_string()),
+            Self::Wgsl(source) => {
+                naga::front::wgsl::parse_str(source).map_err(|err| err.emit_to_string(source))
+            }
+        }
+    }
+}
+
+pub struct CompiledShaderModules {
+    /// The modules by the entry point they were built for, or `None` for a module holding all
+    /// of them.
+    pub(super) named_modules: Vec<(Option<String>, ShaderCode)>,
+}
+
+impl CompiledShaderModules {
+    pub fn module_for_entry_point<'a>(
+        &'a self,
+        wanted_entry: &str,
+    ) -> Result<ShaderModuleSource<'a>, ShaderBuildError> {
+        for (name, code) in &self.named_modules {
+            match name {
+                Some(name) if name != wanted_entry => continue,
+                _ => {
+                    return Ok(ShaderModuleSource {
+                        label: name.as_deref(),
+                        code,
+                    });
+                }
+            }
+        }
+        Err(ShaderBuildError::MissingEntryPoint {
+            entry_point: wanted_entry.to_owned(),
+            available: self
+                .named_modules
+                .iter()
+                .filter_map(|(name, _)| name.clone())
+                .collect(),
+        })
+    }
+
+    /// The entry points of all modules, in the order they are declared.
+    pub fn entry_points(&self) -> Result<Vec<EntryPoint>, ShaderBuildError> {
+        let mut entry_points = Vec::<EntryPoint>::new();
+        for (name, code) in &self.named_modules {
+            let reflected = match code
+                .parse()
+                .and_then(|module| shader_reflection::reflect(&module))
+            {
+                Ok(reflected) => reflected,
+                // Modules using debugPrintf can still list their entry points, only without
+                // their interface.
+                Err(error) => match code {
+                    ShaderCode::SpirV(words)
+                        if shader_reflection::needs_spirv_passthrough(words) =>
+                    {
+                        tracing::debug!("Could not reflect module {name:?}: {error}");
+                        shader_reflection::spirv_entry_points(words)
+                    }
+                    _ => {
+                        return Err(ShaderBuildError::Parse {
+                            module: name.clone(),
+                            error,
+                        })
+                    }
+                },
+            };
+            for entry_point in reflected {
+                if !entry_points
+                    .iter()
+                    .any(|known| known.name == entry_point.name)
+                {
+                    entry_points.push(entry_point);
+                }
+            }
+        }
+        Ok(entry_points)
+    }
+}
+
+/// A module found by [`CompiledShaderModules::module_for_entry_point`].
+pub struct ShaderModuleSource<'a> {
+    label: Option<&'a str>,
+    code: &'a ShaderCode,
+}
+
+impl ShaderModuleSource<'_> {
+    /// Creates the module on `device`. Naga can't translate modules using debugPrintf, so those
+    /// are passed to the driver as they are where the device supports that.
+    pub fn create(&self, device: &wgpu::Device) -> wgpu::ShaderModule {
+        match self.code {
+            ShaderCode::SpirV(words)
+                if shader_reflection::needs_spirv_passthrough(words)
+                    && device
+                        .features()
+                        .contains(wgpu::Features::SPIRV_SHADER_PASSTHROUGH) =>
+            {
+                // SAFETY: wgpu can't validate modules it passes through, but these only differ
+                // from modules it would accept by their debugPrintf instructions.
+                unsafe {
+                    device.create_shader_module_spirv(&wgpu::ShaderModuleDescriptorSpirV {
+                        label: self.label,
+                        source: Cow::Borrowed(words),
+                    })
+                }
+            }
+            code => device.create_shader_module(wgpu::ShaderModuleDescriptor {
+                label: self.label,
+                source: code.source(),
+            }),
+        }
+    }
+}
+
+/// The name of the entry point for `stage` in `entry_points`: `preferred` if there is such an
+/// entry point, otherwise the first one of that stage.
+pub fn pick_entry_point<'a>(
+    entry_points: &'a [EntryPoint],
+    stage: naga::ShaderStage,
+    preferred: &str,
+) -> Result<&'a str, ShaderBuildError> {
+    let mut of_stage = entry_points
+        .iter()
+        .filter(|entry_point| entry_point.stage == stage);
+    of_stage
+        .clone()
+        .find(|entry_point| entry_point.name == preferred)
+        .or_else(|| of_stage.next())
+        .map(|entry_point| entry_point.name.as_str())
+        .ok_or_else(|| ShaderBuildError::MissingEntryPoint {
+            entry_point: preferred.to_owned(),
+            available: entry_points
+                .iter()
+                .map(|entry_point| entry_point.name.clone())
+                .collect(),
+        })
+}
+
+/// Why a shader crate could not be turned into a usable pipeline.
+#[derive(Debug)]
+pub enum ShaderBuildError {
+    /// `spirv-builder` failed, usually because rustc rejected the shader crate.
+    Build {
+        error: String,
+
+        /// Whatever rustc printed while compiling, if it could be captured.
+        diagnostics: String,
+    },
+
+    /// The build succeeded but one of the produced `.spv` files could not be read.
+    ReadModule {
+        path: PathBuf,
+        error: std::io::Error,
+    },
+
+    /// None of the compiled modules provides the entry point the pipeline needs.
+    MissingEntryPoint {
+        entry_point: String,
+        available: Vec<String>,
+    },
+
+    /// A module isn't valid SPIR-V or WGSL, typically one loaded from a file.
+    Parse {
+        /// The entry point the module was built for, if it was built for a single one.
+        module: Option<String>,
+        error: String,
+    },
+
+    /// wgpu rejected the modules when creating the pipeline, e.g. because their bindings don't
+    /// match the ones `Custom3d` provides.
+    Pipeline { error: String },
+
+    /// The shader crate wasn't compiled into this build ahead of time.
+    #[cfg(any(target_os = "android", target_arch = "wasm32"))]
+    NotEmbedded { crate_name: String },
+}
+
+impl ShaderBuildError {
+    /// The compiler output to show alongside the error, if any.
+    pub fn diagnostics(&self) -> Option<&str> {
+        match self {
+            Self::Build { diagnostics, .. } if !diagnostics.is_empty() => Some(diagnostics),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ShaderBuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Build { error, .. } => write!(f, "Shader build failed: {error}"),
+            Self::ReadModule { path, error } => {
+                write!(f, "Failed to read {}: {error}", path.display())
+            }
+            Self::MissingEntryPoint {
+                entry_point,
+                available,
+            } => write!(
+                f,
+                "Entry point {entry_point:?} not found in modules {available:?}"
+            ),
+            Self::Parse {
+                module: Some(module),
+                error,
+            } => write!(f, "Failed to parse module {module:?}: {error}"),
+            Self::Parse {
+                module: None,
+                error,
+            } => write!(f, "Failed to parse module: {error}"),
+            Self::Pipeline { error } => write!(f, "Failed to create the pipeline: {error}"),
+            #[cfg(any(target_os = "android", target_arch = "wasm32"))]
+            Self::NotEmbedded { crate_name } => {
+                write!(
+                    f,
+                    "Shader crate {crate_name:?} is not embedded in this build"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for ShaderBuildError {}
+
+pub type ShaderBuildResult = Result<CompiledShaderModules, ShaderBuildError>;
+
+/// How shader crates are compiled.
+#[derive(Clone, Copy, Default, Hash, PartialEq, Eq)]
+pub struct ShaderBuildOptions {
+    /// Compile every entry point into a module of its own, which only declares the resources
+    /// that entry point uses. Needed when some of the others use features the device lacks.
+    pub multimodule: bool,
+
+    /// Report shader panics with debugPrintf, and allow `spirv_std::macros::debug_printfln!`.
+    /// The output ends up in the log, given the Vulkan validation layers with debugPrintf
+    /// enabled (see `--debug-printf`), and the device has to support
+    /// `wgpu::Features::SPIRV_SHADER_PASSTHROUGH`. Implies `multimodule`.
+    pub debug_printf: bool,
+}
+
+/// Rebuilds the shaders of a [`maybe_watch`] call with other options.
+pub struct ShaderWatcher {
+    #[cfg(not(any(target_os = "android", target_arch = "wasm32")))]
+    rebuild_sender: std::sync::mpsc::Sender<Rebuild>,
+}
+
+impl ShaderWatcher {
+    /// Rebuilds the shaders with `options`, which then apply to all later rebuilds as well.
+    /// Shaders embedded in the build can't be rebuilt, so this does nothing for them.
+    pub fn rebuild(&self, options: ShaderBuildOptions) {
+        #[cfg(not(any(target_os = "android", target_arch = "wasm32")))]
+        let _ = self.rebuild_sender.send(Rebuild::Options(options));
+        #[cfg(any(target_os = "android", target_arch = "wasm32"))]
+        let _ = options;
+    }
+}
+
+/// Why the shaders are rebuilt.
+#[cfg(not(any(target_os = "android", target_arch = "wasm32")))]
+enum Rebuild {
+    SourcesChanged,
+    Options(ShaderBuildOptions),
+}
+
+/// The shader crates compiled by `egui_demo_app_builder`, on targets which can't compile them
+/// at runtime.
+#[cfg(any(target_os = "android", target_arch = "wasm32"))]
+mod embedded {
+    include!(concat!(env!("OUT_DIR"), "/embedded_shaders.rs"));
+}
+
+/// The names of the shader crates embedded in this build.
+#[cfg(any(target_os = "android", target_arch = "wasm32"))]
+pub fn embedded_shader_crates() -> impl Iterator<Item = &'static str> {
+    embedded::EMBEDDED_SHADERS.iter().map(|(name, _)| *name)
+}
+
+/// The directory containing all shader crates, including `shared`.
+#[cfg(not(any(target_os = "android", target_arch = "wasm32")))]
+pub fn shaders_path() -> PathBuf {
+    let manifest_dir = env!("CARGO_MANIFEST_DIR");
+    [manifest_dir, "..", "shaders"]
+        .iter()
+        .copied()
+        .collect::<PathBuf>()
+}
+
+/// The hidden option which makes the app build a shader crate instead of running, see
+/// [`maybe_run_shader_build`].
+#[cfg(not(any(target_os = "android", target_arch = "wasm32")))]
+const BUILD_SHADER_ARG: &str = "--build-shader";
+
+/// The target the shader crates are compiled for.
+#[cfg(not(any(target_os = "android", target_arch = "wasm32")))]
+const TARGET: &str = "spirv-unknown-vulkan1.1";
+
+/// Builds a shader crate and exits if [`maybe_watch`] started the app to do that, and returns
+/// otherwise. Has to be called first thing in `main`.
+///
+/// The build prints its diagnostics to stderr and its result, as JSON, as the last line on
+/// stdout.
+#[cfg(not(any(target_os = "android", target_arch = "wasm32")))]
+pub fn maybe_run_shader_build() {
+    use spirv_builder::{MetadataPrintout, SpirvBuilder};
+
+    let mut args = std::env::args().skip(1);
+    if args.next().as_deref() != Some(BUILD_SHADER_ARG) {
+        return;
+    }
+    let Some(crate_path) = args.next() else {
+        eprintln!("Missing shader crate path for {BUILD_SHADER_ARG}");
+        std::process::exit(2);
+    };
+    let mut options = ShaderBuildOptions::default();
+    for arg in args {
+        match arg.as_str() {
+            "--multimodule" => options.multimodule = true,
+            "--debug-printf" => options.debug_printf = true,
+            _ => {
+                eprintln!("Unknown option {arg} for {BUILD_SHADER_ARG}");
+                std::process::exit(2);
+            }
+        }
+    }
+
+    let result = SpirvBuilder::new(crate_path, TARGET)
+        .print_metadata(MetadataPrintout::None)
+        .shader_panic_strategy(if options.debug_printf {
+            spirv_builder::ShaderPanicStrategy::DebugPrintfThenExit {
+                print_inputs: true,
+                print_backtrace: true,
+            }
+        } else {
+            spirv_builder::ShaderPanicStrategy::SilentExit
+        })
+        // HACK(eddyb) needed because of `debugPrintf` instrumentation limitations
+        // (see https://github.com/KhronosGroup/SPIRV-Tools/issues/4892).
+        .multimodule(options.debug_printf || options.multimodule)
+        .build()
+        .map_err(|error| error.to_string());
+    // `spirv-builder` may have printed a partial line.
+    println!();
+    println!(
+        "{}",
+        serde_json::to_string(&result).expect("CompileResult is serializable")
+    );
+    std::process::exit(i32::from(result.is_err()));
+}
+
+/// Builds the shader crate at `crate_path` in a child process (see [`maybe_run_shader_build`]),
+/// passing its diagnostics through to stderr while keeping a copy for the error.
+///
+/// `spirv-builder` lets cargo and rustc write straight to the stderr of the process it runs in,
+/// and only returns a bare error, so running it in a process of its own is the only way to get
+/// at the diagnostics without redirecting the stderr of the whole app.
+#[cfg(not(any(target_os = "android", target_arch = "wasm32")))]
+fn build_in_child_process(
+    crate_path: &std::path::Path,
+    options: ShaderBuildOptions,
+) -> Result<spirv_builder::CompileResult, ShaderBuildError> {
+    use std::{
+        io::{Read, Write},
+        process::{Command, Stdio},
+    };
+
+    let build_error =
+        |error: String, diagnostics: String| ShaderBuildError::Build { error, diagnostics };
+    let mut command = Command::new(
+        std::env::current_exe()
+            .map_err(|err| build_error(format!("Could not find the app: {err}"), String::new()))?,
+    );
+    command.arg(BUILD_SHADER_ARG).arg(crate_path);
+    if options.multimodule {
+        command.arg("--multimodule");
+    }
+    if options.debug_printf {
+        command.arg("--debug-printf");
+    }
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|err| {
+            build_error(
+                format!("Could not start the shader build: {err}"),
+                String::new(),
+            )
+        })?;
+
+    let mut stderr = child.stderr.take().expect("stderr is piped");
+    let stderr_reader = std::thread::spawn(move || {
+        let mut captured = Vec::new();
+        let mut buf = [0; 4096];
+        while let Ok(n @ 1..) = stderr.read(&mut buf) {
+            let _ = std::io::stderr().write_all(&buf[..n]);
+            captured.extend_from_slice(&buf[..n]);
+        }
+        String::from_utf8_lossy(&captured).into_owned()
+    });
+    let mut stdout = String::new();
+    let _ = child
+        .stdout
+        .take()
+        .expect("stdout is piped")
+        .read_to_string(&mut stdout);
+    let status = child.wait();
+    let diagnostics = stderr_reader.join().unwrap_or_default();
+
+    let result = stdout.lines().last().and_then(|line| {
+        serde_json::from_str::<Result<spirv_builder::CompileResult, String>>(line).ok()
+    });
+    match (result, status) {
+        (Some(result), _) => result.map_err(|error| build_error(error, diagnostics)),
+        (None, Ok(status)) => Err(build_error(
+            format!("The shader build exited with {status}"),
+            diagnostics,
+        )),
+        (None, Err(err)) => Err(build_error(
+            format!("The shader build failed: {err}"),
+            diagnostics,
+        )),
+    }
+}
+
+/// Builds the shader crate `crate_name` and passes the result to `on_build`.
+///
+/// Natively the build runs on a background thread, as it takes a while, which then keeps
+/// watching the shaders and passes the result of every rebuild to `on_build` as well. Each build
+/// runs in a child process of the app, which has to call [`maybe_run_shader_build`] for that.
+pub fn maybe_watch(
+    options: ShaderBuildOptions,
+    crate_name: &str,
+    mut on_build: impl FnMut(ShaderBuildResult) + Send + 'static,
+) -> ShaderWatcher {
+    #[cfg(not(any(target_os = "android", target_arch = "wasm32")))]
+    {
+        use super::shader_cache::ShaderCache;
+        use spirv_builder::CompileResult;
+        // Hack: spirv_builder builds into a custom directory if running under cargo, to not
+        // deadlock, and the default target directory if not. However, packages like `proc-macro2`
+        // have different configurations when being built here vs. when building
+        // rustc_codegen_spirv normally, so we *want* to build into a separate target directory, to
+        // not have to rebuild half the crate graph every time we run. So, pretend we're running
+        // under cargo by setting these environment variables, which the build process inherits.
+        std::env::set_var("OUT_DIR", env!("OUT_DIR"));
+        std::env::set_var("PROFILE", env!("PROFILE"));
+        let shaders_path = shaders_path();
+        let crate_path = shaders_path.join(crate_name);
+
+        let cache = ShaderCache::new(&shaders_path, crate_name, TARGET);
+        let build = move |options: ShaderBuildOptions| {
+            // Hash the sources again for every build, they are what changed when rebuilding.
+            let key = cache
+                .key(options)
+                .map_err(|err| tracing::warn!("Could not hash the sources of the shaders: {err}"))
+                .ok();
+            if let Some(compiled_shader_modules) = key.and_then(|key| cache.load(key)) {
+                return Ok(compiled_shader_modules);
+            }
+            let result =
+                build_in_child_process(&crate_path, options).and_then(handle_compile_result);
+            if let (Ok(compiled_shader_modules), Some(key)) = (&result, key) {
+                cache.store(key, compiled_shader_modules);
+            }
+            result
+        };
+        fn handle_compile_result(compile_result: CompileResult) -> ShaderBuildResult {
+            let load_spv_module = |path: PathBuf| {
+                let data = std::fs::read(&path)
+                    .map_err(|error| ShaderBuildError::ReadModule { path, error })?;
+                // FIXME(eddyb) this reallocates all the data pointlessly, the words could be
+                // read straight from the file.
+                let spirv = Cow::Owned(wgpu::util::make_spirv_raw(&data).into_owned());
+                Ok(ShaderCode::SpirV(spirv))
+            };
+            Ok(CompiledShaderModules {
+                named_modules: match compile_result.module {
+                    spirv_builder::ModuleResult::SingleModule(path) => {
+                        vec![(None, load_spv_module(path)?)]
+                    }
+                    spirv_builder::ModuleResult::MultiModule(modules) => modules
+                        .into_iter()
+                        .map(|(name, path)| Ok((Some(name), load_spv_module(path)?)))
+                        .collect::<Result<_, _>>()?,
+                },
+            })
+        }
+        let (rebuild_sender, rebuild_receiver) = std::sync::mpsc::channel();
+        let watch_sender = rebuild_sender.clone();
+        std::thread::spawn(move || {
+            let mut options = options;
+            on_build(build(options));
+            // Unlike `SpirvBuilder::watch`, keep watching (and reporting) after failed builds,
+            // both for the initial one and for any that follow.
+            let _watcher = watch_path(&shaders_path, watch_sender);
+            while let Ok(rebuild) = rebuild_receiver.recv() {
+                // Editors tend to touch a file several times per save, give them a moment
+                // so that we only rebuild once.
+                std::thread::sleep(std::time::Duration::from_millis(100));
+                for rebuild in std::iter::once(rebuild).chain(rebuild_receiver.try_iter()) {
+                    if let Rebuild::Options(new_options) = rebuild {
+                        options = new_options;
+                    }
+                }
+                on_build(build(options));
+            }
+        });
+        ShaderWatcher { rebuild_sender }
+    }
+    #[cfg(any(target_os = "android", target_arch = "wasm32"))]
+    {
+        // The embedded shaders are always built with a module per entry point, which works
+        // whatever the options ask for.
+        let _ = options;
+        on_build(load_embedded(crate_name));
+        ShaderWatcher {}
+    }
+}
+
+/// The modules `egui_demo_app_builder` compiled for `crate_name`.
+#[cfg(any(target_os = "android", target_arch = "wasm32"))]
+fn load_embedded(crate_name: &str) -> ShaderBuildResult {
+    let (_, modules) = embedded::EMBEDDED_SHADERS
+        .iter()
+        .find(|(name, _)| *name == crate_name)
+        .ok_or_else(|| ShaderBuildError::NotEmbedded {
+            crate_name: crate_name.to_owned(),
+        })?;
+    Ok(CompiledShaderModules {
+        named_modules: modules
+            .iter()
+            .map(|(entry_point, spirv)| {
+                let module = ShaderCode::SpirV(wgpu::util::make_spirv_raw(spirv));
+                (Some((*entry_point).to_owned()), module)
+            })
+            .collect(),
+    })
+}
+
+/// Asks for a rebuild whenever something under `path` changes, for as long as the returned
+/// watcher is kept.
+#[cfg(not(any(target_os = "android", target_arch = "wasm32")))]
+fn watch_path(
+    path: &std::path::Path,
+    rebuild_sender: std::sync::mpsc::Sender<Rebuild>,
+) -> Option<notify::RecommendedWatcher> {
+    use notify::{Event, EventKind, RecursiveMode, Watcher};
+
+    let mut watcher =
+        notify::recommended_watcher(move |event: notify::Result<Event>| match event {
+            Ok(event) if matches!(event.kind, EventKind::Access(_)) => (),
+            Ok(_) => {
+                let _ = rebuild_sender.send(Rebuild::SourcesChanged);
+            }
+            Err(e) => tracing::warn!("notify error: {e:?}"),
+        })
+        .expect("Could create watcher");
+    if let Err(e) = watcher.watch(path, RecursiveMode::Recursive) {
+        tracing::error!("Could not watch {}: {e}", path.display());
+        return None;
+    }
+    Some(watcher)
+}
@@ -0,0 +1,246 @@
+//! Compiled SPIR-V kept on disk between runs, so that unchanged shader crates load without
+//! running `rustc_codegen_spirv` again.
+//!
+//! Every crate has a directory of its own in the cache, holding the modules of its most recent
+//! build under a key hashed from everything they are built from.
+
+use std::{
+    borrow::Cow,
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+    io,
+    path::{Path, PathBuf},
+};
+
+use eframe::egui_wgpu::wgpu;
+
+use super::shader_builder::{is_spirv, CompiledShaderModules, ShaderCode};
+
+/// The module of builds that aren't split by entry point.
+const SINGLE_MODULE: &str = "module.spv";
+
+/// The directory holding a module per entry point, named after it.
+const ENTRY_POINTS: &str = "entry_points";
+
+pub struct ShaderCache {
+    /// Where the builds of this crate are cached.
+    dir: PathBuf,
+
+    /// The shader crate and the libraries it uses, whose sources go into the key.
+    source_dirs: Vec<PathBuf>,
+
+    /// The lock file of the workspace, which pins the versions of rust-gpu (and so of the
+    /// compiler) and of the libraries the shaders use.
+    lock_file: PathBuf,
+
+    /// Hash of everything else the modules depend on, like the target, apart from the build
+    /// options.
+    settings_hash: u64,
+}
+
+impl ShaderCache {
+    pub fn new(shaders_path: &Path, crate_name: &str, target: &str) -> Self {
+        let mut hasher = DefaultHasher::new();
+        target.hash(&mut hasher);
+        Self {
+            dir: cache_path().join(crate_name),
+            source_dirs: vec![shaders_path.join(crate_name), shaders_path.join("shared")],
+            lock_file: shaders_path.join("..").join("Cargo.lock"),
+            settings_hash: hasher.finish(),
+        }
+    }
+
+    /// The key of the modules built with `options` from the sources and dependencies as they
+    /// are now.
+    ///
+    /// This uses the hasher of `std`, which is only stable within a Rust version, so a new
+    /// toolchain starts out with a cold cache.
+    pub fn key(&self, options: impl Hash) -> io::Result<u64> {
+        let mut hasher = DefaultHasher::new();
+        self.settings_hash.hash(&mut hasher);
+        options.hash(&mut hasher);
+        std::fs::read(&self.lock_file)?.hash(&mut hasher);
+        for dir in &self.source_dirs {
+            hash_dir(dir, dir, &mut hasher)?;
+        }
+        Ok(hasher.finish())
+    }
+
+    /// The modules stored under `key`, if there are any.
+    pub fn load(&self, key: u64) -> Option<CompiledShaderModules> {
+        let dir = self.dir.join(format!("{key:016x}"));
+        if !dir.is_dir() {
+            return None;
+        }
+        let result = read_modules(&dir);
+        match result {
+            Ok(named_modules) => Some(CompiledShaderModules { named_modules }),
+            Err(err) => {
+                tracing::warn!(
+                    "Could not load cached shaders from {}: {err}",
+                    dir.display()
+                );
+                None
+            }
+        }
+    }
+
+    /// Stores `modules` under `key`, replacing earlier builds of the crate.
+    pub fn store(&self, key: u64, modules: &CompiledShaderModules) {
+        if let Err(err) = self.try_store(key, modules) {
+            tracing::warn!("Could not cache shaders in {}: {err}", self.dir.display());
+        }
+    }
+
+    fn try_store(&self, key: u64, modules: &CompiledShaderModules) -> io::Result<()> {
+        let name = format!("{key:016x}");
+        // Write everything next to where it goes first, so that other instances of the app
+        // never load a partially written build.
+        let staging = self.dir.join(format!("{name}.{}.tmp", std::process::id()));
+        std::fs::create_dir_all(&staging)?;
+        for (entry_point, module) in &modules.named_modules {
+            // Builds only ever produce SPIR-V.
+            let ShaderCode::SpirV(words) = module else {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "only SPIR-V modules can be cached",
+                ));
+            };
+            let path = match entry_point {
+                Some(entry_point) => {
+                    let dir = staging.join(ENTRY_POINTS);
+                    std::fs::create_dir_all(&dir)?;
+                    dir.join(format!("{entry_point}.spv"))
+                }
+                None => staging.join(SINGLE_MODULE),
+            };
+            std::fs::write(path, bytemuck::cast_slice(words))?;
+        }
+        for entry in std::fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            // Leave the builds other instances are still staging alone.
+            if path.extension().is_some_and(|extension| extension == "tmp") {
+                continue;
+            }
+            std::fs::remove_dir_all(path)?;
+        }
+        std::fs::rename(staging, self.dir.join(name))
+    }
+}
+
+/// The directory of the cache, next to the build of the app itself.
+fn cache_path() -> PathBuf {
+    // `OUT_DIR` is `$target/$profile/build/egui_demo_app-$hash/out`.
+    let out_dir = Path::new(env!("OUT_DIR"));
+    out_dir
+        .ancestors()
+        .nth(3)
+        .unwrap_or(out_dir)
+        .join("shader-cache")
+}
+
+/// Hashes the paths (relative to `root`) and contents of all files under `dir`.
+fn hash_dir(root: &Path, dir: &Path, hasher: &mut impl Hasher) -> io::Result<()> {
+    let mut entries = std::fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<io::Result<Vec<_>>>()?;
+    // Directory listings come in no particular order.
+    entries.sort();
+    for path in entries {
+        if path.is_dir() {
+            if path.file_name().is_some_and(|name| name != "target") {
+                hash_dir(root, &path, hasher)?;
+            }
+        } else {
+            path.strip_prefix(root).unwrap_or(&path).hash(hasher);
+            std::fs::read(&path)?.hash(hasher);
+        }
+    }
+    Ok(())
+}
+
+fn read_modules(dir: &Path) -> io::Result<Vec<(Option<String>, ShaderCode)>> {
+    let single_module = dir.join(SINGLE_MODULE);
+    if single_module.is_file() {
+        return Ok(vec![(None, read_module(&single_module)?)]);
+    }
+    let mut modules = Vec::new();
+    for entry in std::fs::read_dir(dir.join(ENTRY_POINTS))? {
+        let path = entry?.path();
+        let Some(entry_point) = path.file_stem().and_then(|stem| stem.to_str()) else {
+            continue;
+        };
+        modules.push((Some(entry_point.to_owned()), read_module(&path)?));
+    }
+    Ok(modules)
+}
+
+fn read_module(path: &Path) -> io::Result<ShaderCode> {
+    let data = std::fs::read(path)?;
+    // `make_spirv_raw` panics on anything that isn't SPIR-V.
+    if !is_spirv(&data) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "not a SPIR-V module",
+        ));
+    }
+    Ok(ShaderCode::SpirV(Cow::Owned(
+        wgpu::util::make_spirv_raw(&data).into_owned(),
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A `shaders/` directory with a shader crate and `shared`, in a fresh temporary directory.
+    fn shaders_dir(name: &str) -> PathBuf {
+        let shaders_path = std::env::temp_dir()
+            .join(format!("shader-cache-{name}-{}", std::process::id()))
+            .join("shaders");
+        let _ = std::fs::remove_dir_all(&shaders_path);
+        for (path, contents) in [
+            ("../Cargo.lock", "version = 3"),
+            ("mandelbrot/Cargo.toml", "[package]"),
+            ("mandelbrot/src/lib.rs", "fn main_fs() {}"),
+            ("shared/src/lib.rs", "pub struct ShaderConstants;"),
+        ] {
+            let path = shaders_path.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, contents).unwrap();
+        }
+        shaders_path
+    }
+
+    #[test]
+    fn key_follows_sources_and_options() {
+        let shaders_path = shaders_dir("key");
+        let cache = ShaderCache::new(&shaders_path, "mandelbrot", "spirv-unknown-vulkan1.1");
+        let key = cache.key(false).unwrap();
+        assert_eq!(cache.key(false).unwrap(), key);
+        assert_ne!(cache.key(true).unwrap(), key);
+
+        // Build output doesn't count, the sources of the crate and of `shared` do.
+        std::fs::create_dir_all(shaders_path.join("mandelbrot/target")).unwrap();
+        std::fs::write(shaders_path.join("mandelbrot/target/out.spv"), "").unwrap();
+        assert_eq!(cache.key(false).unwrap(), key);
+        std::fs::write(shaders_path.join("shared/src/lib.rs"), "").unwrap();
+        let shared_changed = cache.key(false).unwrap();
+        assert_ne!(shared_changed, key);
+        std::fs::rename(
+            shaders_path.join("mandelbrot/src/lib.rs"),
+            shaders_path.join("mandelbrot/src/main.rs"),
+        )
+        .unwrap();
+        assert_ne!(cache.key(false).unwrap(), shared_changed);
+
+        // So do the versions of everything in `Cargo.lock`, even when it changes later on.
+        let before = cache.key(false).unwrap();
+        std::fs::write(shaders_path.join("../Cargo.lock"), "version = 4").unwrap();
+        assert_ne!(cache.key(false).unwrap(), before);
+        std::fs::remove_file(shaders_path.join("../Cargo.lock")).unwrap();
+        assert!(cache.key(false).is_err());
+
+        std::fs::remove_dir_all(shaders_path.parent().unwrap()).unwrap();
+    }
+}
@@ -0,0 +1,25 @@
+//! Finds the shader crates under `shaders/`.
+//!
+//! `egui_demo_app_builder` includes this file as well, so that the app and the shaders it
+//! embeds agree on which crates are shaders. Keep it free of dependencies for that reason.
+
+use std::{io, path::Path};
+
+/// Support libraries under `shaders/` that have no entry points of their own.
+const LIBRARY_CRATES: &[&str] = &["shared"];
+
+/// The names of the shader crates in `shaders_path`, sorted.
+pub fn discover_shader_crates(shaders_path: &Path) -> io::Result<Vec<String>> {
+    let mut crates = Vec::new();
+    for entry in std::fs::read_dir(shaders_path)? {
+        let entry = entry?;
+        let Ok(name) = entry.file_name().into_string() else {
+            continue;
+        };
+        if entry.path().join("Cargo.toml").is_file() && !LIBRARY_CRATES.contains(&name.as_str()) {
+            crates.push(name);
+        }
+    }
+    crates.sort();
+    Ok(crates)
+}
@@ -0,0 +1,32 @@
+//! Prebuilt SPIR-V or WGSL shaders loaded from files, so that shaders can be viewed without the
+//! rust-gpu toolchain.
+//!
+//! The shader has to provide what `Custom3d` draws with: a vertex entry point covering the
+//! painted area with the 3 vertices of a single triangle, and a fragment entry point. Those
+//! named `main_vs` and `main_fs` (or `main_fs_uniform` without push constants) are preferred,
+//! otherwise the first of each stage is used.
+
+use std::borrow::Cow;
+
+use eframe::egui_wgpu::wgpu;
+
+use super::shader_builder::{
+    is_spirv, CompiledShaderModules, ShaderBuildError, ShaderBuildResult, ShaderCode,
+};
+
+/// Loads the contents of a `.spv` or `.wgsl` file. Which of the two it is is told from the
+/// contents rather than the file name.
+pub fn load(bytes: &[u8]) -> ShaderBuildResult {
+    let code = if is_spirv(bytes) {
+        ShaderCode::SpirV(Cow::Owned(wgpu::util::make_spirv_raw(bytes).into_owned()))
+    } else {
+        let source = std::str::from_utf8(bytes).map_err(|_| ShaderBuildError::Parse {
+            module: None,
+            error: "neither SPIR-V nor WGSL".to_owned(),
+        })?;
+        ShaderCode::Wgsl(Cow::Owned(source.to_owned()))
+    };
+    Ok(CompiledShaderModules {
+        named_modules: vec![(None, code)],
+    })
+}
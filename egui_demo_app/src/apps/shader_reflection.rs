@@ -0,0 +1,306 @@
+//! What the entry points of compiled modules expect from the pipeline, reflected with naga.
+
+use std::fmt::Write as _;
+
+/// An entry point of a compiled module.
+#[derive(Clone, Debug)]
+pub struct EntryPoint {
+    pub name: String,
+    pub stage: naga::ShaderStage,
+
+    /// The values passed in, like `@location(0) vec2<f32>` or `@builtin(Position) vec4<f32>`.
+    pub inputs: Vec<String>,
+
+    /// The values passed on to the next stage, or to the render targets.
+    pub outputs: Vec<String>,
+
+    /// The resources the entry point uses, like `uniform @group(0) @binding(1) Camera`.
+    pub resources: Vec<String>,
+
+    /// The workgroup size of compute entry points.
+    pub workgroup_size: Option<[u32; 3]>,
+}
+
+/// Reflects all entry points of `module`, in the order they are declared.
+pub fn reflect(module: &naga::Module) -> Result<Vec<EntryPoint>, String> {
+    // Validating is left to wgpu, this only needs the analysis of which globals are used.
+    let info = naga::valid::Validator::new(
+        naga::valid::ValidationFlags::empty(),
+        naga::valid::Capabilities::all(),
+    )
+    .validate(module)
+    .map_err(|err| err.into_inner().to_string())?;
+
+    let entry_points = module
+        .entry_points
+        .iter()
+        .enumerate()
+        .map(|(index, entry_point)| {
+            let function = &entry_point.function;
+            let mut inputs = Vec::new();
+            for argument in &function.arguments {
+                describe_io(module, argument.ty, argument.binding.as_ref(), &mut inputs);
+            }
+            let mut outputs = Vec::new();
+            if let Some(result) = &function.result {
+                describe_io(module, result.ty, result.binding.as_ref(), &mut outputs);
+            }
+            let function_info = info.get_entry_point(index);
+            let resources = module
+                .global_variables
+                .iter()
+                .filter(|&(handle, _)| !function_info[handle].is_empty())
+                .filter_map(|(_, global)| describe_resource(module, global))
+                .collect();
+            EntryPoint {
+                name: entry_point.name.clone(),
+                stage: entry_point.stage,
+                inputs,
+                outputs,
+                resources,
+                workgroup_size: (entry_point.stage == naga::ShaderStage::Compute)
+                    .then_some(entry_point.workgroup_size),
+            }
+        });
+    Ok(entry_points.collect())
+}
+
+/// Describes the value of type `ty` passed with `binding`, or every member of it if it is a
+/// struct of values with bindings of their own.
+fn describe_io(
+    module: &naga::Module,
+    ty: naga::Handle<naga::Type>,
+    binding: Option<&naga::Binding>,
+    descriptions: &mut Vec<String>,
+) {
+    match binding {
+        Some(naga::Binding::BuiltIn(built_in)) => {
+            let built_in = match built_in {
+                naga::BuiltIn::Position { .. } => "Position".to_owned(),
+                built_in => format!("{built_in:?}"),
+            };
+            descriptions.push(format!("@builtin({built_in}) {}", type_name(module, ty)));
+        }
+        Some(naga::Binding::Location { location, .. }) => {
+            descriptions.push(format!("@location({location}) {}", type_name(module, ty)));
+        }
+        None => {
+            if let naga::TypeInner::Struct { members, .. } = &module.types[ty].inner {
+                for member in members {
+                    describe_io(module, member.ty, member.binding.as_ref(), descriptions);
+                }
+            }
+        }
+    }
+}
+
+/// Describes `global` if it is provided by the pipeline, rather than private to the shader.
+fn describe_resource(module: &naga::Module, global: &naga::GlobalVariable) -> Option<String> {
+    let mut description = match global.space {
+        naga::AddressSpace::Uniform => "uniform",
+        naga::AddressSpace::Storage { .. } => "storage",
+        naga::AddressSpace::Handle => "handle",
+        naga::AddressSpace::PushConstant => "push_constant",
+        naga::AddressSpace::Function
+        | naga::AddressSpace::Private
+        | naga::AddressSpace::WorkGroup => return None,
+    }
+    .to_owned();
+    if let Some(binding) = &global.binding {
+        let _ = write!(
+            description,
+            " @group({}) @binding({})",
+            binding.group, binding.binding
+        );
+    }
+    let _ = write!(description, " {}", type_name(module, global.ty));
+    Some(description)
+}
+
+/// The name of `ty`, or how WGSL spells it for the common unnamed types.
+fn type_name(module: &naga::Module, ty: naga::Handle<naga::Type>) -> String {
+    let ty = &module.types[ty];
+    if let Some(name) = &ty.name {
+        return name.clone();
+    }
+    match ty.inner {
+        naga::TypeInner::Scalar { kind, width } => scalar_name(kind, width),
+        naga::TypeInner::Vector { size, kind, width } => {
+            format!("vec{}<{}>", size as u8, scalar_name(kind, width))
+        }
+        naga::TypeInner::Matrix {
+            columns,
+            rows,
+            width,
+        } => format!(
+            "mat{}x{}<{}>",
+            columns as u8,
+            rows as u8,
+            scalar_name(naga::ScalarKind::Float, width)
+        ),
+        naga::TypeInner::Array { base, .. } | naga::TypeInner::BindingArray { base, .. } => {
+            format!("array<{}>", type_name(module, base))
+        }
+        naga::TypeInner::Image { .. } => "texture".to_owned(),
+        naga::TypeInner::Sampler { .. } => "sampler".to_owned(),
+        _ => "?".to_owned(),
+    }
+}
+
+fn scalar_name(kind: naga::ScalarKind, width: naga::Bytes) -> String {
+    match kind {
+        naga::ScalarKind::Sint => format!("i{}", width * 8),
+        naga::ScalarKind::Uint => format!("u{}", width * 8),
+        naga::ScalarKind::Float => format!("f{}", width * 8),
+        naga::ScalarKind::Bool => "bool".to_owned(),
+    }
+}
+
+const OP_EXT_INST_IMPORT: u32 = 11;
+const OP_ENTRY_POINT: u32 = 15;
+
+/// Whether a SPIR-V module imports non-semantic instructions like debugPrintf, which naga can't
+/// translate, so that wgpu has to pass the module to the driver as it is.
+pub fn needs_spirv_passthrough(words: &[u32]) -> bool {
+    spirv_instructions(words).any(|(opcode, operands)| {
+        // The operands are the result id and the name of the instruction set.
+        opcode == OP_EXT_INST_IMPORT
+            && operands
+                .get(1..)
+                .is_some_and(|name| spirv_string(name).starts_with("NonSemantic."))
+    })
+}
+
+/// The entry points declared in a SPIR-V module, for modules [`reflect`] can't handle. Their
+/// interface is left empty.
+pub fn spirv_entry_points(words: &[u32]) -> Vec<EntryPoint> {
+    spirv_instructions(words)
+        .filter(|&(opcode, _)| opcode == OP_ENTRY_POINT)
+        .filter_map(|(_, operands)| {
+            // The operands are the execution model, the function, the name and the interface.
+            let stage = match operands.first()? {
+                0 => naga::ShaderStage::Vertex,
+                4 => naga::ShaderStage::Fragment,
+                5 => naga::ShaderStage::Compute,
+                _ => return None,
+            };
+            Some(EntryPoint {
+                name: spirv_string(operands.get(2..)?),
+                stage,
+                inputs: Vec::new(),
+                outputs: Vec::new(),
+                resources: Vec::new(),
+                workgroup_size: None,
+            })
+        })
+        .collect()
+}
+
+/// The opcodes and operands of the instructions of a SPIR-V module.
+fn spirv_instructions(words: &[u32]) -> impl Iterator<Item = (u32, &[u32])> {
+    // Skip the header.
+    let mut rest = words.get(5..).unwrap_or_default();
+    std::iter::from_fn(move || {
+        let word_count = (*rest.first()? >> 16) as usize;
+        if word_count == 0 || word_count > rest.len() {
+            return None;
+        }
+        let (instruction, tail) = rest.split_at(word_count);
+        rest = tail;
+        Some((instruction[0] & 0xffff, &instruction[1..]))
+    })
+}
+
+/// Decodes the nul terminated string at the start of `words`.
+fn spirv_string(words: &[u32]) -> String {
+    let bytes = words
+        .iter()
+        .flat_map(|word| word.to_le_bytes())
+        .take_while(|&byte| byte != 0)
+        .collect::<Vec<_>>();
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The header of a SPIR-V module: magic, version, generator, bound and schema.
+    const HEADER: [u32; 5] = [0x0723_0203, 0x0001_0300, 0, 16, 0];
+
+    /// An instruction with `operands`, followed by the string operand `name`.
+    fn instruction(opcode: u32, operands: &[u32], name: &str) -> Vec<u32> {
+        let mut bytes = name.as_bytes().to_vec();
+        bytes.resize(name.len() / 4 * 4 + 4, 0);
+        let name_words = bytes
+            .chunks(4)
+            .map(|chunk| u32::from_le_bytes(chunk.try_into().unwrap()));
+        let mut words = vec![0];
+        words.extend_from_slice(operands);
+        words.extend(name_words);
+        words[0] = (words.len() as u32) << 16 | opcode;
+        words
+    }
+
+    fn module(instructions: &[&[u32]]) -> Vec<u32> {
+        let mut words = HEADER.to_vec();
+        for instruction in instructions {
+            words.extend_from_slice(instruction);
+        }
+        words
+    }
+
+    #[test]
+    fn instructions() {
+        let import = instruction(OP_EXT_INST_IMPORT, &[1], "GLSL.std.450");
+        let entry_point = instruction(OP_ENTRY_POINT, &[4, 2], "main_fs");
+        let words = module(&[&import, &entry_point]);
+        let decoded: Vec<_> = spirv_instructions(&words).collect();
+        assert_eq!(
+            decoded,
+            [
+                (OP_EXT_INST_IMPORT, &import[1..]),
+                (OP_ENTRY_POINT, &entry_point[1..])
+            ]
+        );
+        assert_eq!(spirv_string(&import[2..]), "GLSL.std.450");
+
+        // Stops at instructions claiming more words than are left, or none at all.
+        assert_eq!(spirv_instructions(&words[..words.len() - 1]).count(), 1);
+        assert_eq!(spirv_instructions(&module(&[&[0]])).count(), 0);
+        assert_eq!(spirv_instructions(&HEADER[..3]).count(), 0);
+    }
+
+    #[test]
+    fn passthrough_for_non_semantic_imports() {
+        let glsl = instruction(OP_EXT_INST_IMPORT, &[1], "GLSL.std.450");
+        let debug_printf = instruction(OP_EXT_INST_IMPORT, &[2], "NonSemantic.DebugPrintf");
+        assert!(!needs_spirv_passthrough(&module(&[&glsl])));
+        assert!(needs_spirv_passthrough(&module(&[&glsl, &debug_printf])));
+        // Only imports count, not other instructions naming the instruction set.
+        let entry_point = instruction(OP_ENTRY_POINT, &[4, 2], "NonSemantic.DebugPrintf");
+        assert!(!needs_spirv_passthrough(&module(&[&entry_point])));
+    }
+
+    #[test]
+    fn entry_points_of_spirv() {
+        let words = module(&[
+            &instruction(OP_EXT_INST_IMPORT, &[1], "NonSemantic.DebugPrintf"),
+            &instruction(OP_ENTRY_POINT, &[0, 2], "main_vs"),
+            &instruction(OP_ENTRY_POINT, &[4, 3], "main_fs"),
+            // Geometry shaders aren't supported.
+            &instruction(OP_ENTRY_POINT, &[3, 4], "main_gs"),
+        ]);
+        let entry_points: Vec<_> = spirv_entry_points(&words)
+            .into_iter()
+            .map(|entry_point| (entry_point.name, entry_point.stage))
+            .collect();
+        assert_eq!(
+            entry_points,
+            [
+                ("main_vs".to_owned(), naga::ShaderStage::Vertex),
+                ("main_fs".to_owned(), naga::ShaderStage::Fragment),
+            ]
+        );
+    }
+}
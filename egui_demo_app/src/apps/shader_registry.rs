@@ -0,0 +1,54 @@
+//! Finds the shader crates that can be shown as apps.
+
+/// Environment variable holding a comma separated list of shader crates to load,
+/// instead of every crate found under `shaders/`.
+pub const SHADER_CRATES_VAR: &str = "SHADER_CRATES";
+
+/// Shader crates rendering a 3D scene through `shared::camera::Camera`, which start out
+/// navigated with the camera rather than by panning and zooming the plane.
+const CAMERA_CRATES: &[&str] = &["raymarch"];
+
+/// Shader crates drawing escape-time fractals, the only ones reading the palette, iteration
+/// limit, Julia set and reference orbit parts of `ShaderConstants`.
+const FRACTAL_CRATES: &[&str] = &["mandelbrot"];
+
+/// The names of the shader crates to build, in the order their tabs are shown.
+pub fn shader_crates() -> Vec<String> {
+    if let Ok(configured) = std::env::var(SHADER_CRATES_VAR) {
+        return configured
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(ToOwned::to_owned)
+            .collect();
+    }
+    discover_shader_crates()
+}
+
+/// Whether `crate_name` is one of the [`CAMERA_CRATES`].
+pub fn uses_camera(crate_name: &str) -> bool {
+    CAMERA_CRATES.contains(&crate_name)
+}
+
+/// Whether `crate_name` is one of the [`FRACTAL_CRATES`].
+pub fn is_fractal(crate_name: &str) -> bool {
+    FRACTAL_CRATES.contains(&crate_name)
+}
+
+#[cfg(not(any(target_os = "android", target_arch = "wasm32")))]
+fn discover_shader_crates() -> Vec<String> {
+    let shaders_path = super::shader_builder::shaders_path();
+    super::shader_discovery::discover_shader_crates(&shaders_path).unwrap_or_else(|err| {
+        tracing::error!("Could not read {}: {err}", shaders_path.display());
+        Vec::new()
+    })
+}
+
+// There is no `shaders/` directory to look into on these targets, only the shaders compiled
+// into the build.
+#[cfg(any(target_os = "android", target_arch = "wasm32"))]
+fn discover_shader_crates() -> Vec<String> {
+    super::shader_builder::embedded_shader_crates()
+        .map(ToOwned::to_owned)
+        .collect()
+}
@@ -0,0 +1,123 @@
+//! Renders a shader crate into a PNG, without opening a window.
+//!
+//! The shader is evaluated on the CPU (see [`egui_demo_app::cpu_renderer`]), so this works
+//! without a GPU and gives the same image on every machine.
+
+use std::{error::Error, fs::File, io::BufWriter, path::PathBuf};
+
+use egui_demo_app::{camera_controller::CameraController, cpu_renderer};
+use shared::ShaderConstants;
+use spirv_std::glam::Vec2;
+
+const USAGE: &str = "\
+Usage: render <SHADER_CRATE> <OUTPUT.png> [OPTIONS]
+
+Options:
+  --size <WIDTHxHEIGHT>     Image size in pixels [default: 1280x1024]
+  --zoom <ZOOM>             ShaderConstants::zoom [default: 1]
+  --translate <X,Y>         ShaderConstants::translate_x/y, in pixels [default: 0,0]
+  --time <SECONDS>          ShaderConstants::time [default: 0]
+  --palette <INDEX>         ShaderConstants::palette [default: 0]
+  --iterations <N>          ShaderConstants::max_iterations [default: 100]
+  --julia <X,Y>             Render the Julia set for X + Yi instead
+";
+
+struct Args {
+    crate_name: String,
+    output: PathBuf,
+    constants: ShaderConstants,
+}
+
+fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Args, String> {
+    let mut constants = ShaderConstants::new(1280, 1024);
+    let mut positional = Vec::new();
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        if arg == "--help" || arg == "-h" {
+            print!("{USAGE}");
+            std::process::exit(0);
+        }
+        if !arg.starts_with("--") {
+            positional.push(arg);
+            continue;
+        }
+        let value = args
+            .next()
+            .ok_or_else(|| format!("Missing value for {arg}"))?;
+        let invalid = || format!("Invalid value for {arg}: {value:?}");
+        match arg.as_str() {
+            "--size" => {
+                let (width, height) = value.split_once('x').ok_or_else(invalid)?;
+                constants.width = width.parse().map_err(|_| invalid())?;
+                constants.height = height.parse().map_err(|_| invalid())?;
+            }
+            "--zoom" => constants.zoom = value.parse().map_err(|_| invalid())?,
+            "--translate" => {
+                [constants.translate_x, constants.translate_y] =
+                    parse_pair(&value).ok_or_else(invalid)?;
+            }
+            "--time" => constants.time = value.parse().map_err(|_| invalid())?,
+            "--palette" => constants.palette = value.parse().map_err(|_| invalid())?,
+            "--iterations" => constants.max_iterations = value.parse().map_err(|_| invalid())?,
+            "--julia" => {
+                [constants.julia_x, constants.julia_y] = parse_pair(&value).ok_or_else(invalid)?;
+                constants.julia = 1;
+            }
+            _ => return Err(format!("Unknown option {arg}")),
+        }
+    }
+
+    match <[String; 2]>::try_from(positional) {
+        Ok([crate_name, output]) => Ok(Args {
+            crate_name,
+            output: output.into(),
+            constants,
+        }),
+        Err(_) => Err("Expected a shader crate and an output path".to_owned()),
+    }
+}
+
+/// Parses `"x,y"`.
+fn parse_pair(value: &str) -> Option<[f32; 2]> {
+    let (x, y) = value.split_once(',')?;
+    Some([x.trim().parse().ok()?, y.trim().parse().ok()?])
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args = match parse_args(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("{err}\n\n{USAGE}");
+            std::process::exit(2);
+        }
+    };
+    let shader = cpu_renderer::fragment_shader(&args.crate_name)
+        .ok_or_else(|| format!("{} has no CPU build", args.crate_name))?;
+
+    let constants = &args.constants;
+    let size = [constants.width as usize, constants.height as usize];
+    let viewport = egui::Rect::from_min_size(
+        egui::Pos2::ZERO,
+        egui::vec2(constants.width as f32, constants.height as f32),
+    );
+    let camera = CameraController::default().camera(viewport);
+    let image = cpu_renderer::render(shader, constants, &[], &camera, Vec2::ZERO, size);
+
+    let mut encoder = png::Encoder::new(
+        BufWriter::new(File::create(&args.output)?),
+        constants.width,
+        constants.height,
+    );
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header()?;
+    let rgba: Vec<u8> = image
+        .pixels
+        .iter()
+        .flat_map(|color| color.to_array())
+        .collect();
+    writer.write_image_data(&rgba)?;
+    writer.finish()?;
+    Ok(())
+}
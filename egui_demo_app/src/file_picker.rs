@@ -0,0 +1,181 @@
+//! A small file browser for opening shader files, as egui has none of its own and native file
+//! dialogs would add a dependency on the platform's toolkit (GTK on Linux).
+
+use std::path::{Path, PathBuf};
+
+/// The extensions of the files [`crate::apps::Custom3d::from_shader_file`] can show.
+const SHADER_EXTENSIONS: [&str; 2] = ["spv", "wgsl"];
+
+/// A window listing the subdirectories and shader files of a directory.
+pub struct FilePicker {
+    dir: PathBuf,
+
+    /// The subdirectories of `dir`, followed by its shader files, or why it couldn't be read.
+    entries: Result<Vec<DirEntry>, String>,
+
+    /// The path in the text field, a file picked from `entries` or typed in.
+    path: String,
+}
+
+struct DirEntry {
+    name: String,
+    is_dir: bool,
+}
+
+impl FilePicker {
+    /// Starts out showing `dir`.
+    pub fn new(dir: PathBuf) -> Self {
+        let mut slf = Self {
+            dir: PathBuf::new(),
+            entries: Ok(Vec::new()),
+            path: String::new(),
+        };
+        slf.change_dir(dir);
+        slf
+    }
+
+    fn change_dir(&mut self, dir: PathBuf) {
+        self.entries = list_dir(&dir).map_err(|err| err.to_string());
+        self.path = dir.display().to_string();
+        self.dir = dir;
+    }
+
+    /// Shows the picker, and returns the file to open once one is picked. Sets `open` to false
+    /// when the picker should close, whether a file was picked or not.
+    pub fn show(&mut self, ctx: &egui::Context, open: &mut bool) -> Option<PathBuf> {
+        let mut picked = None;
+        let mut window_open = true;
+        egui::Window::new("Open shader")
+            .open(&mut window_open)
+            .collapsible(false)
+            .default_size([400.0, 300.0])
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    if ui.button("⬆").on_hover_text("Parent directory").clicked() {
+                        if let Some(parent) = self.dir.parent() {
+                            self.change_dir(parent.to_owned());
+                        }
+                    }
+                    ui.label(self.dir.display().to_string());
+                });
+                ui.separator();
+
+                let mut change_dir = None;
+                egui::ScrollArea::vertical()
+                    .max_height(ui.available_height() - 60.0)
+                    .auto_shrink([false, false])
+                    .show(ui, |ui| match &self.entries {
+                        Ok(entries) if entries.is_empty() => {
+                            ui.weak("No directories or .spv/.wgsl files");
+                        }
+                        Ok(entries) => {
+                            for entry in entries {
+                                let path = self.dir.join(&entry.name);
+                                let label = if entry.is_dir {
+                                    format!("📁 {}", entry.name)
+                                } else {
+                                    format!("📄 {}", entry.name)
+                                };
+                                let selected = Path::new(&self.path) == path;
+                                let response = ui.selectable_label(selected, label);
+                                if entry.is_dir && response.clicked() {
+                                    change_dir = Some(path);
+                                } else if response.double_clicked() {
+                                    picked = Some(path);
+                                } else if response.clicked() {
+                                    self.path = path.display().to_string();
+                                }
+                            }
+                        }
+                        Err(err) => {
+                            ui.colored_label(ui.visuals().error_fg_color, err);
+                        }
+                    });
+                if let Some(dir) = change_dir {
+                    self.change_dir(dir);
+                }
+
+                ui.separator();
+                let response =
+                    ui.add(egui::TextEdit::singleline(&mut self.path).desired_width(f32::INFINITY));
+                let entered =
+                    response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                ui.horizontal(|ui| {
+                    if ui.button("Open").clicked() || entered {
+                        let path = PathBuf::from(&self.path);
+                        if path.is_dir() {
+                            self.change_dir(path);
+                        } else {
+                            picked = Some(path);
+                        }
+                    }
+                    if ui.button("Cancel").clicked() {
+                        *open = false;
+                    }
+                });
+            });
+        if !window_open || picked.is_some() {
+            *open = false;
+        }
+        picked
+    }
+}
+
+/// The subdirectories of `dir` and the shader files in it, each sorted by name. Hidden entries
+/// are left out.
+fn list_dir(dir: &Path) -> std::io::Result<Vec<DirEntry>> {
+    let mut entries = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if name.starts_with('.') {
+            continue;
+        }
+        // Follows symlinks, unlike `DirEntry::file_type`.
+        let is_dir = entry.path().is_dir();
+        let is_shader = Path::new(&name).extension().is_some_and(|extension| {
+            SHADER_EXTENSIONS
+                .iter()
+                .any(|shader| extension.eq_ignore_ascii_case(shader))
+        });
+        if is_dir || is_shader {
+            entries.push(DirEntry { name, is_dir });
+        }
+    }
+    entries.sort_by(|a, b| b.is_dir.cmp(&a.is_dir).then_with(|| a.name.cmp(&b.name)));
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lists_directories_then_shader_files() {
+        let dir = std::env::temp_dir().join(format!("file-picker-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        for path in [
+            "b.wgsl",
+            "a.SPV",
+            "notes.txt",
+            ".hidden.spv",
+            "shaders/main.spv",
+        ] {
+            let path = dir.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, "").unwrap();
+        }
+
+        let entries = list_dir(&dir).unwrap();
+        let names = entries
+            .iter()
+            .map(|entry| (entry.name.as_str(), entry.is_dir))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            [("shaders", true), ("a.SPV", false), ("b.wgsl", false)]
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -2,12 +2,19 @@
 #![allow(clippy::missing_errors_doc)]
 
 mod apps;
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) mod file_picker;
 pub(crate) mod frame_history;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod shader_log;
 mod wrap_app;
 
 #[cfg(target_arch = "wasm32")]
 use eframe::web::AppRunnerRef;
 
+#[cfg(not(any(target_os = "android", target_arch = "wasm32")))]
+pub use apps::maybe_run_shader_build;
+pub use apps::{camera_controller, cpu_renderer, perturbation};
 pub use wrap_app::WrapApp;
 
 // ----------------------------------------------------------------------------
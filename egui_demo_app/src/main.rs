@@ -2,8 +2,60 @@
 
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")] // hide console window on Windows in release
 
+use std::path::PathBuf;
+
+const USAGE: &str = "\
+Usage: egui_demo_app [OPTIONS]
+
+Options:
+  --shader <FILE>    Also show a prebuilt .spv or .wgsl shader, may be repeated
+  --debug-printf     Build the shaders with debugPrintf, logging shader panics and prints
+";
+
+#[derive(Default)]
+struct Args {
+    shader_files: Vec<PathBuf>,
+    debug_printf: bool,
+}
+
+fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Args, String> {
+    let mut parsed = Args::default();
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        if arg == "--help" || arg == "-h" {
+            print!("{USAGE}");
+            std::process::exit(0);
+        }
+        if arg == "--debug-printf" {
+            parsed.debug_printf = true;
+            continue;
+        }
+        let value = args
+            .next()
+            .ok_or_else(|| format!("Missing value for {arg}"))?;
+        match arg.as_str() {
+            "--shader" => parsed.shader_files.push(value.into()),
+            _ => return Err(format!("Unknown option {arg}")),
+        }
+    }
+    Ok(parsed)
+}
+
 // When compiling natively:
 fn main() -> Result<(), eframe::Error> {
+    // The app builds its shaders by running itself with a hidden option.
+    #[cfg(not(target_os = "android"))]
+    egui_demo_app::maybe_run_shader_build();
+
+    let args = match parse_args(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("{err}\n\n{USAGE}");
+            std::process::exit(2);
+        }
+    };
+
     {
         // Silence wgpu log spam (https://github.com/gfx-rs/wgpu/issues/3206)
         let mut rust_log = std::env::var("RUST_LOG").unwrap_or_else(|_| "info".to_owned());
@@ -15,26 +67,63 @@ fn main() -> Result<(), eframe::Error> {
         std::env::set_var("RUST_LOG", rust_log);
     }
 
+    if args.debug_printf {
+        enable_debug_printf();
+    }
+
     // Log to stdout (if you run with `RUST_LOG=debug`).
-    tracing_subscriber::fmt::init();
+    egui_demo_app::shader_log::init();
+
+    let wgpu_options = egui_wgpu::WgpuConfiguration::default();
+    // Without a wgpu adapter, fall back to drawing the UI with OpenGL and evaluating the shaders
+    // on the CPU.
+    let adapter_limits = adapter_limits(&wgpu_options);
+    let renderer = if adapter_limits.is_some() {
+        eframe::Renderer::Wgpu
+    } else {
+        tracing::warn!("No wgpu adapter found, rendering on the CPU");
+        eframe::Renderer::Glow
+    };
+    // Shaders read their constants from push constants where available, and from a uniform
+    // buffer otherwise. Whether `ShaderConstants` fit is checked once the device exists, as
+    // `Custom3d` has to do that anyway on the web.
+    let max_push_constant_size = adapter_limits.as_ref().map_or(0, |(features, limits)| {
+        if features.contains(wgpu::Features::PUSH_CONSTANTS) {
+            limits.max_push_constant_size
+        } else {
+            0
+        }
+    });
+
+    // Shaders built with debugPrintf can only be passed through to the driver, as naga can't
+    // translate them. Ask for that whenever possible, so that debugPrintf can be turned on
+    // without restarting.
+    let spirv_shader_passthrough = adapter_limits
+        .map_or(wgpu::Features::empty(), |(features, _)| {
+            features.intersection(wgpu::Features::SPIRV_SHADER_PASSTHROUGH)
+        });
 
     let options = eframe::NativeOptions {
         drag_and_drop_support: true,
 
         initial_window_size: Some([1280.0, 1024.0].into()),
 
-        renderer: eframe::Renderer::Wgpu,
+        renderer,
 
         wgpu_options: egui_wgpu::WgpuConfiguration {
             device_descriptor: wgpu::DeviceDescriptor {
                 label: None,
-                features: wgpu::Features::PUSH_CONSTANTS,
+                features: if max_push_constant_size > 0 {
+                    wgpu::Features::PUSH_CONSTANTS
+                } else {
+                    wgpu::Features::empty()
+                } | spirv_shader_passthrough,
                 limits: wgpu::Limits {
-                    max_push_constant_size: 128,
+                    max_push_constant_size,
                     ..Default::default()
                 },
             },
-            ..Default::default()
+            ..wgpu_options
         },
 
         ..Default::default()
@@ -42,6 +131,47 @@ fn main() -> Result<(), eframe::Error> {
     eframe::run_native(
         "egui demo app",
         options,
-        Box::new(|cc| Box::new(egui_demo_app::WrapApp::new(cc))),
+        Box::new(move |cc| {
+            Box::new(
+                egui_demo_app::WrapApp::with_debug_printf(cc, args.debug_printf)
+                    .with_shader_files(cc, &args.shader_files),
+            )
+        }),
     )
 }
+
+/// Enables the Vulkan validation layers and their debugPrintf support through the environment
+/// (see `spirv_builder::ShaderPanicStrategy::DebugPrintfThenExit`), unless configured already.
+fn enable_debug_printf() {
+    for (var, value) in [
+        ("VK_LOADER_LAYERS_ENABLE", "VK_LAYER_KHRONOS_validation"),
+        (
+            "VK_LAYER_ENABLES",
+            "VK_VALIDATION_FEATURE_ENABLE_DEBUG_PRINTF_EXT",
+        ),
+    ] {
+        if std::env::var_os(var).is_none() {
+            std::env::set_var(var, value);
+        }
+    }
+    // wgpu only forwards the messages of the validation layers, which carry the output of
+    // debugPrintf, to the log in debug builds. Have the layers print them otherwise.
+    if !cfg!(debug_assertions) && std::env::var_os("DEBUG_PRINTF_TO_STDOUT").is_none() {
+        std::env::set_var("DEBUG_PRINTF_TO_STDOUT", "1");
+    }
+}
+
+/// The features and limits of the adapter eframe will pick with `options`, if there is one.
+fn adapter_limits(
+    options: &egui_wgpu::WgpuConfiguration,
+) -> Option<(wgpu::Features, wgpu::Limits)> {
+    let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+        backends: options.backends,
+        ..Default::default()
+    });
+    let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+        power_preference: options.power_preference,
+        ..Default::default()
+    }))?;
+    Some((adapter.features(), adapter.limits()))
+}
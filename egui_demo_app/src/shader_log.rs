@@ -0,0 +1,153 @@
+//! Logging to stdout, with the output of debugPrintf in shaders (including the panic messages of
+//! shaders built with debugPrintf) picked out of the Vulkan validation messages carrying it.
+
+use std::{env, fmt};
+
+use tracing::field::{Field, Visit};
+use tracing_subscriber::{
+    filter::{LevelFilter, Targets},
+    layer::{Context, Layer, SubscriberExt},
+    util::SubscriberInitExt,
+};
+
+/// The target shader output is logged with.
+pub const SHADER_TARGET: &str = "shader";
+
+/// Where wgpu forwards the validation messages carrying debugPrintf output.
+const VALIDATION_TARGET: &str = "wgpu_hal";
+
+/// Installs the global subscriber, which also receives the records of the `log` crate, filtered
+/// by `RUST_LOG` like `tracing_subscriber::fmt::init` does.
+pub fn init() {
+    let rust_log = rust_log_targets();
+    // debugPrintf output comes in info messages, which `RUST_LOG` mustn't filter out before
+    // `DebugPrintfLayer` has picked it out of them. The layer applies `RUST_LOG` to the rest.
+    let mut filter = rust_log.clone();
+    if !rust_log.would_enable(VALIDATION_TARGET, &tracing::Level::INFO) {
+        filter = filter.with_target(VALIDATION_TARGET, LevelFilter::INFO);
+    }
+    tracing_subscriber::registry()
+        .with(tracing_subscriber::fmt::layer())
+        .with(DebugPrintfLayer { rust_log })
+        .with(filter)
+        .init();
+}
+
+/// The levels `RUST_LOG` enables, or info and up if it isn't set.
+fn rust_log_targets() -> Targets {
+    let default = Targets::new().with_default(LevelFilter::INFO);
+    match env::var("RUST_LOG") {
+        Ok(rust_log) => rust_log.parse().unwrap_or_else(|err| {
+            eprintln!("Ignoring `RUST_LOG={rust_log:?}`: {err}");
+            default
+        }),
+        Err(env::VarError::NotPresent) => default,
+        Err(err) => {
+            eprintln!("Ignoring `RUST_LOG`: {err}");
+            default
+        }
+    }
+}
+
+/// Logs the debugPrintf output in the validation messages wgpu forwards to `log` under
+/// [`SHADER_TARGET`] instead, as errors for shader panics.
+struct DebugPrintfLayer {
+    /// What to log of the other validation messages.
+    rust_log: Targets,
+}
+
+impl<S: tracing::Subscriber> Layer<S> for DebugPrintfLayer {
+    fn event_enabled(&self, event: &tracing::Event<'_>, _ctx: Context<'_, S>) -> bool {
+        let mut visitor = LogRecordVisitor::default();
+        event.record(&mut visitor);
+        if !visitor.target.starts_with(VALIDATION_TARGET) {
+            return true;
+        }
+        let Some(output) = debug_printf_output(&visitor.message) else {
+            return self
+                .rust_log
+                .would_enable(&visitor.target, event.metadata().level());
+        };
+        // Disabling the original message is the only way to replace it, so log the output
+        // right here.
+        if output.contains("panicked at") {
+            tracing::error!(target: SHADER_TARGET, "{output}");
+        } else {
+            tracing::info!(target: SHADER_TARGET, "{output}");
+        }
+        false
+    }
+}
+
+/// The fields of the events the `log` crate's records are turned into.
+#[derive(Default)]
+struct LogRecordVisitor {
+    target: String,
+    message: String,
+}
+
+impl Visit for LogRecordVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        match field.name() {
+            "log.target" => self.target = value.to_owned(),
+            "message" => self.message = value.to_owned(),
+            _ => {}
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            self.message = format!("{value:?}");
+        }
+    }
+}
+
+/// What was printed, if `message` is a validation message carrying debugPrintf output.
+///
+/// Those look like `... [ UNASSIGNED-DEBUG-PRINTF ] Object 0: ... | MessageID = 0x... | output`,
+/// with `WARNING-DEBUG-PRINTF` in later versions of the validation layers.
+fn debug_printf_output(message: &str) -> Option<&str> {
+    if !message.contains("DEBUG-PRINTF") {
+        return None;
+    }
+    let output = message
+        .split_once("MessageID = ")
+        .and_then(|(_, rest)| rest.split_once("| "))
+        .map_or(message, |(_, output)| output);
+    Some(output.trim())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn debug_printf_output_of_validation_messages() {
+        let message = "VALIDATION [UNASSIGNED-DEBUG-PRINTF (0x92394c89)]\n\
+            \tValidation Information: [ UNASSIGNED-DEBUG-PRINTF ] Object 0: handle = \
+            0x55d9a4a25f40, type = VK_OBJECT_TYPE_DEVICE; | MessageID = 0x92394c89 | \
+            zoom: 0.5 iterations: 100 ";
+        assert_eq!(
+            debug_printf_output(message),
+            Some("zoom: 0.5 iterations: 100")
+        );
+
+        // Later versions of the validation layers.
+        let message = "[ WARNING-DEBUG-PRINTF ] | MessageID = 0x76589099 | \
+            panicked at shaders/mandelbrot/src/lib.rs:42:5";
+        assert_eq!(
+            debug_printf_output(message),
+            Some("panicked at shaders/mandelbrot/src/lib.rs:42:5")
+        );
+
+        // Without the expected layout the whole message is kept.
+        assert_eq!(
+            debug_printf_output(" DEBUG-PRINTF: hello "),
+            Some("DEBUG-PRINTF: hello")
+        );
+        assert_eq!(
+            debug_printf_output("[ VUID-vkCmdDraw-None-02699 ] | MessageID = 0x1 | oops"),
+            None
+        );
+    }
+}
@@ -1,5 +1,8 @@
 #[cfg(target_arch = "wasm32")]
 use core::any::Any;
+use std::path::Path;
+
+use eframe::egui_wgpu;
 
 /// The state that we persist (serialize).
 #[derive(Default)]
@@ -13,20 +16,41 @@ pub struct State {
 pub struct WrapApp {
     state: State,
 
-    custom3d: Option<crate::apps::Custom3d>,
+    /// Name, anchor and app for every shader crate (see [`crate::apps::shader_registry`]),
+    /// followed by the shader files that were opened.
+    custom3d: Vec<(String, String, crate::apps::Custom3d)>,
 
     frame_history: crate::frame_history::FrameHistory,
+
+    /// The picker opened with the "Open shader" button, while it is open.
+    #[cfg(not(target_arch = "wasm32"))]
+    file_picker: Option<crate::file_picker::FilePicker>,
 }
 
 impl WrapApp {
     pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
+        Self::with_debug_printf(cc, false)
+    }
+
+    /// Like [`Self::new`], but builds the shader crates with debugPrintf if `debug_printf` is set,
+    /// so that shader panics end up in the log.
+    pub fn with_debug_printf(cc: &eframe::CreationContext<'_>, debug_printf: bool) -> Self {
         #[allow(unused_mut)]
         let mut slf = Self {
             state: State::default(),
 
-            custom3d: crate::apps::Custom3d::new(cc),
+            custom3d: crate::apps::shader_registry::shader_crates()
+                .into_iter()
+                .filter_map(|crate_name| {
+                    let custom3d = crate::apps::Custom3d::new(cc, &crate_name, debug_printf)?;
+                    Some((format!("🔺 {crate_name}"), crate_name, custom3d))
+                })
+                .collect(),
 
             frame_history: Default::default(),
+
+            #[cfg(not(target_arch = "wasm32"))]
+            file_picker: None,
         };
 
         #[cfg(feature = "persistence")]
@@ -39,13 +63,97 @@ impl WrapApp {
         slf
     }
 
+    /// Also opens the prebuilt `.spv` or `.wgsl` shader files at `paths`, selecting the last.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn with_shader_files(
+        mut self,
+        cc: &eframe::CreationContext<'_>,
+        paths: &[std::path::PathBuf],
+    ) -> Self {
+        for path in paths {
+            self.open_shader_path(cc.wgpu_render_state.as_ref(), path);
+        }
+        self
+    }
+
+    /// Shows the `.spv` or `.wgsl` shader file at `path`, see [`Self::open_shader_file`].
+    #[cfg(not(target_arch = "wasm32"))]
+    fn open_shader_path(
+        &mut self,
+        render_state: Option<&egui_wgpu::RenderState>,
+        path: &std::path::Path,
+    ) {
+        match std::fs::read(path) {
+            Ok(bytes) => self.open_shader_file(render_state, &file_name(path), &bytes),
+            Err(err) => tracing::error!("Could not read {}: {err}", path.display()),
+        }
+    }
+
+    /// Shows the file picker while it is open, and the shader file picked with it.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn show_file_picker(&mut self, ctx: &egui::Context, frame: &eframe::Frame) {
+        let Some(file_picker) = &mut self.file_picker else {
+            return;
+        };
+        let mut open = true;
+        let picked = file_picker.show(ctx, &mut open);
+        if !open {
+            self.file_picker = None;
+        }
+        if let Some(path) = picked {
+            self.open_shader_path(frame.wgpu_render_state(), &path);
+        }
+    }
+
+    /// Shows the shader in `bytes` in a tab named `name`, replacing any earlier file of that name.
+    fn open_shader_file(
+        &mut self,
+        render_state: Option<&egui_wgpu::RenderState>,
+        name: &str,
+        bytes: &[u8],
+    ) {
+        let Some(render_state) = render_state else {
+            tracing::warn!("Shader files can only be shown with wgpu, not opening {name}");
+            return;
+        };
+        let custom3d = crate::apps::Custom3d::from_shader_file(render_state, name, bytes);
+        let tab = (format!("📄 {name}"), name.to_owned(), custom3d);
+        match self
+            .custom3d
+            .iter_mut()
+            .find(|(_, anchor, _)| anchor == name)
+        {
+            Some(existing) => *existing = tab,
+            None => self.custom3d.push(tab),
+        }
+        self.state.selected_anchor = name.to_owned();
+    }
+
+    fn open_dropped_files(&mut self, ctx: &egui::Context, frame: &eframe::Frame) {
+        for file in ctx.input(|i| i.raw.dropped_files.clone()) {
+            // Browsers hand over the contents, native platforms the path.
+            let (name, bytes) = match (file.bytes, file.path) {
+                (Some(bytes), _) => (file.name, bytes.to_vec()),
+                (None, Some(path)) => match std::fs::read(&path) {
+                    Ok(bytes) => (file_name(&path), bytes),
+                    Err(err) => {
+                        tracing::error!("Could not read {}: {err}", path.display());
+                        continue;
+                    }
+                },
+                (None, None) => continue,
+            };
+            self.open_shader_file(frame.wgpu_render_state(), &name, &bytes);
+        }
+    }
+
     fn apps_iter_mut(&mut self) -> impl Iterator<Item = (&str, &str, &mut dyn eframe::App)> {
         let mut vec = vec![];
 
-        if let Some(custom3d) = &mut self.custom3d {
+        for (name, anchor, custom3d) in &mut self.custom3d {
             vec.push((
-                "🔺 3D painting",
-                "custom3d",
+                name.as_str(),
+                anchor.as_str(),
                 custom3d as &mut dyn eframe::App,
             ));
         }
@@ -88,7 +196,11 @@ impl eframe::App for WrapApp {
 
         ctx.request_repaint();
 
+        self.open_dropped_files(ctx, frame);
+        #[cfg(not(target_arch = "wasm32"))]
+        self.show_file_picker(ctx, frame);
         self.show_selected_app(ctx, frame);
+        preview_files_being_dropped(ctx);
 
         // On web, the browser controls `pixels_per_point`.
         if !frame.is_web() {
@@ -113,7 +225,9 @@ impl WrapApp {
             }
         }
         if !found_anchor {
-            self.state.selected_anchor = "custom3d".into();
+            if let Some((_name, anchor, _app)) = self.custom3d.first() {
+                self.state.selected_anchor = anchor.clone();
+            }
         }
     }
 
@@ -134,6 +248,44 @@ impl WrapApp {
 
         ui.separator();
 
+        #[cfg(not(target_arch = "wasm32"))]
+        if ui
+            .button("📂 Open shader…")
+            .on_hover_text("Show a prebuilt .spv or .wgsl shader, or drop one onto the window")
+            .clicked()
+        {
+            let dir = std::env::current_dir().unwrap_or_default();
+            self.file_picker = Some(crate::file_picker::FilePicker::new(dir));
+        }
+
+        ui.separator();
+
         ui.label(format!("FPS: {:.1}", self.frame_history.fps()));
     }
 }
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .unwrap_or(path.as_os_str())
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Tells what dropping the files being dragged over the window does.
+fn preview_files_being_dropped(ctx: &egui::Context) {
+    use egui::{Align2, Color32, Id, LayerId, Order, TextStyle};
+
+    if ctx.input(|i| i.raw.hovered_files.is_empty()) {
+        return;
+    }
+    let painter = ctx.layer_painter(LayerId::new(Order::Foreground, Id::new("file_drop_target")));
+    let screen_rect = ctx.screen_rect();
+    painter.rect_filled(screen_rect, 0.0, Color32::from_black_alpha(192));
+    painter.text(
+        screen_rect.center(),
+        Align2::CENTER_CENTER,
+        "Drop a .spv or .wgsl shader to view it",
+        TextStyle::Heading.resolve(&ctx.style()),
+        Color32::WHITE,
+    );
+}
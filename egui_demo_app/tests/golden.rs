@@ -0,0 +1,267 @@
+//! Golden image tests: renders the shader crates on the CPU and compares the output with the
+//! reference images in `tests/golden/`.
+//!
+//! Run with `UPDATE_GOLDEN=1` to (re)write the reference images after an intended change.
+//! On failure the actual output and a diff image are written next to the test binaries, see
+//! the failure message for where.
+
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter},
+    path::{Path, PathBuf},
+};
+
+use egui::{Color32, ColorImage};
+use egui_demo_app::{camera_controller::CameraController, cpu_renderer, perturbation};
+use shared::{palette, ShaderConstants};
+use spirv_std::glam::{DVec2, Vec2};
+
+/// How much any channel of a pixel may differ from the reference, out of 255.
+const CHANNEL_TOLERANCE: u8 = 2;
+
+/// Fraction of pixels that may exceed [`CHANNEL_TOLERANCE`]. Pixels right at the boundary of
+/// the set can escape an iteration earlier or later depending on the platform's `ln` and `cos`.
+const MAX_MISMATCHED_PIXELS: f32 = 0.002;
+
+const WIDTH: u32 = 160;
+const HEIGHT: u32 = 120;
+
+struct Fixture {
+    name: &'static str,
+    crate_name: &'static str,
+    constants: ShaderConstants,
+
+    /// The point on the complex plane at the center of the image, for deep zooms that iterate
+    /// relative to a reference orbit there. `translate` is ignored in that case.
+    reference: Option<DVec2>,
+}
+
+fn constants() -> ShaderConstants {
+    ShaderConstants::new(WIDTH, HEIGHT)
+}
+
+fn fixtures() -> Vec<Fixture> {
+    vec![
+        Fixture {
+            name: "mandelbrot_overview",
+            crate_name: "mandelbrot",
+            constants: ShaderConstants {
+                zoom: 3.0,
+                translate_x: -30.0,
+                ..constants()
+            },
+            reference: None,
+        },
+        Fixture {
+            name: "mandelbrot_seahorse_valley",
+            crate_name: "mandelbrot",
+            constants: ShaderConstants {
+                zoom: 0.05,
+                // Centers -0.745 + 0.1i, as zoom * translate / height.
+                translate_x: -1788.0,
+                translate_y: 240.0,
+                palette: palette::ULTRA_FRACTAL,
+                max_iterations: 300,
+                ..constants()
+            },
+            reference: None,
+        },
+        Fixture {
+            name: "julia",
+            crate_name: "mandelbrot",
+            constants: ShaderConstants {
+                zoom: 3.0,
+                palette: palette::OCEAN,
+                julia: 1,
+                julia_x: -0.8,
+                julia_y: 0.156,
+                ..constants()
+            },
+            reference: None,
+        },
+        Fixture {
+            name: "mandelbrot_deep_zoom",
+            crate_name: "mandelbrot",
+            constants: ShaderConstants {
+                zoom: 2e-5,
+                palette: palette::SUNSET,
+                max_iterations: 2000,
+                ..constants()
+            },
+            reference: Some(DVec2::new(-0.743_643_887_037_151, 0.131_825_904_205_33)),
+        },
+        Fixture {
+            name: "raymarch",
+            crate_name: "raymarch",
+            constants: ShaderConstants {
+                time: 1.0,
+                ..constants()
+            },
+            reference: None,
+        },
+    ]
+}
+
+fn render(fixture: &Fixture) -> ColorImage {
+    let shader = cpu_renderer::fragment_shader(fixture.crate_name)
+        .unwrap_or_else(|| panic!("{} has no CPU build", fixture.crate_name));
+    let mut constants = fixture.constants;
+    let reference_orbit = match fixture.reference {
+        Some(reference) => {
+            let (z0, c) = if constants.julia != 0 {
+                (
+                    reference,
+                    DVec2::new(constants.julia_x as f64, constants.julia_y as f64),
+                )
+            } else {
+                (DVec2::ZERO, reference)
+            };
+            let orbit = perturbation::reference_orbit(z0, c, constants.max_iterations);
+            constants.translate_x = 0.0;
+            constants.translate_y = 0.0;
+            constants.reference_orbit_len = orbit.len() as u32;
+            orbit
+        }
+        None => Vec::new(),
+    };
+    let size = [constants.width as usize, constants.height as usize];
+    let viewport = egui::Rect::from_min_size(
+        egui::Pos2::ZERO,
+        egui::vec2(constants.width as f32, constants.height as f32),
+    );
+    let camera = CameraController::default().camera(viewport);
+    cpu_renderer::render(
+        shader,
+        &constants,
+        &reference_orbit,
+        &camera,
+        Vec2::ZERO,
+        size,
+    )
+}
+
+fn golden_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden")
+}
+
+fn read_png(path: &Path) -> std::io::Result<ColorImage> {
+    let decoder = png::Decoder::new(BufReader::new(File::open(path)?));
+    let mut reader = decoder.read_info()?;
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buf)?;
+    assert_eq!(
+        (info.color_type, info.bit_depth),
+        (png::ColorType::Rgba, png::BitDepth::Eight),
+        "{} is not an 8 bit RGBA image",
+        path.display()
+    );
+    let size = [info.width as usize, info.height as usize];
+    Ok(ColorImage::from_rgba_unmultiplied(
+        size,
+        &buf[..info.buffer_size()],
+    ))
+}
+
+fn write_png(path: &Path, image: &ColorImage) {
+    let file = File::create(path).unwrap_or_else(|e| panic!("{}: {e}", path.display()));
+    let [width, height] = image.size;
+    let mut encoder = png::Encoder::new(BufWriter::new(file), width as u32, height as u32);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let rgba: Vec<u8> = image.pixels.iter().flat_map(|c| c.to_array()).collect();
+    encoder
+        .write_header()
+        .and_then(|mut writer| writer.write_image_data(&rgba))
+        .unwrap_or_else(|e| panic!("{}: {e}", path.display()));
+}
+
+fn channel_difference(a: Color32, b: Color32) -> u8 {
+    let (a, b) = (a.to_array(), b.to_array());
+    (0..4).map(|i| a[i].abs_diff(b[i])).max().unwrap_or(0)
+}
+
+/// Mismatched pixels in red, brighter the larger the difference, over a faded copy of the
+/// expected image.
+fn diff_image(actual: &ColorImage, expected: &ColorImage) -> ColorImage {
+    let pixels = actual
+        .pixels
+        .iter()
+        .zip(&expected.pixels)
+        .map(|(&actual, &expected)| {
+            let difference = channel_difference(actual, expected);
+            if difference > CHANNEL_TOLERANCE {
+                Color32::from_rgb(128 + difference / 2, 0, 0)
+            } else {
+                let [r, g, b, _] = expected.to_array().map(|c| c / 4);
+                Color32::from_rgb(r, g, b)
+            }
+        })
+        .collect();
+    ColorImage {
+        size: actual.size,
+        pixels,
+    }
+}
+
+/// Compares `actual` with the reference image, returning what went wrong (if anything).
+fn check(name: &str, actual: &ColorImage) -> Result<(), String> {
+    let golden_path = golden_dir().join(format!("{name}.png"));
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        write_png(&golden_path, actual);
+        return Ok(());
+    }
+
+    let output_dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("golden");
+    std::fs::create_dir_all(&output_dir).unwrap();
+    let actual_path = output_dir.join(format!("{name}.actual.png"));
+    let diff_path = output_dir.join(format!("{name}.diff.png"));
+
+    let expected = match read_png(&golden_path) {
+        Ok(expected) => expected,
+        Err(err) => {
+            write_png(&actual_path, actual);
+            return Err(format!(
+                "{name}: could not read {}: {err}. Run with UPDATE_GOLDEN=1 to create it.",
+                golden_path.display()
+            ));
+        }
+    };
+    if expected.size != actual.size {
+        write_png(&actual_path, actual);
+        return Err(format!(
+            "{name}: rendered {:?} pixels, but the reference image has {:?}, see {}",
+            actual.size,
+            expected.size,
+            actual_path.display()
+        ));
+    }
+
+    let mismatched = actual
+        .pixels
+        .iter()
+        .zip(&expected.pixels)
+        .filter(|(&actual, &expected)| channel_difference(actual, expected) > CHANNEL_TOLERANCE)
+        .count();
+    let allowed = (MAX_MISMATCHED_PIXELS * actual.pixels.len() as f32) as usize;
+    if mismatched > allowed {
+        write_png(&actual_path, actual);
+        write_png(&diff_path, &diff_image(actual, &expected));
+        return Err(format!(
+            "{name}: {mismatched} pixels differ from the reference image (at most {allowed} may), \
+             see {} and {}",
+            actual_path.display(),
+            diff_path.display()
+        ));
+    }
+    Ok(())
+}
+
+#[test]
+fn shaders_match_golden_images() {
+    // Check every fixture before failing, so one run reports (and writes diffs for) all of them.
+    let failures: Vec<String> = fixtures()
+        .iter()
+        .filter_map(|fixture| check(fixture.name, &render(fixture)).err())
+        .collect();
+    assert!(failures.is_empty(), "\n{}", failures.join("\n"));
+}
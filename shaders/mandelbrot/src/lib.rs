@@ -2,7 +2,7 @@
 
 use complex::Complex;
 use shared::*;
-use spirv_std::glam::{vec2, vec3, Vec2, Vec3, Vec4};
+use spirv_std::glam::{vec2, Vec2, Vec3, Vec4};
 #[cfg_attr(not(target_arch = "spirv"), allow(unused_imports))]
 use spirv_std::num_traits::Float;
 use spirv_std::spirv;
@@ -11,30 +11,114 @@ use spirv_std::spirv;
 pub fn main_fs(
     #[spirv(frag_coord)] frag_coord: Vec4,
     #[spirv(push_constant)] constants: &ShaderConstants,
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 0)] reference_orbit: &[Vec2],
     output: &mut Vec4,
 ) {
+    let uv = plane_point(frag_coord, constants);
+    let (z, n) = if constants.reference_orbit_len == 0 {
+        iterate(uv, constants)
+    } else {
+        // `uv` is relative to the reference orbit, see `ShaderConstants::reference_orbit_len`.
+        let (dz, dc) = if constants.julia != 0 {
+            (uv, Complex::ZERO)
+        } else {
+            (Complex::ZERO, uv)
+        };
+        iterate_perturbed(
+            reference_orbit,
+            constants.reference_orbit_len as usize,
+            dz,
+            dc,
+            constants.max_iterations,
+        )
+    };
+    *output = color(z, n, constants);
+}
+
+/// [`main_fs`] for adapters without push constants. Those (like WebGL) often lack storage
+/// buffers as well, so this one takes no reference orbit and always iterates in plain f32,
+/// which the host accounts for by not deep zooming.
+#[spirv(fragment)]
+pub fn main_fs_uniform(
+    #[spirv(frag_coord)] frag_coord: Vec4,
+    #[spirv(uniform, descriptor_set = 0, binding = 2)] constants: &ShaderConstants,
+    output: &mut Vec4,
+) {
+    let (z, n) = iterate(plane_point(frag_coord, constants), constants);
+    *output = color(z, n, constants);
+}
+
+/// The point of the complex plane `frag_coord` shows.
+fn plane_point(frag_coord: Vec4, constants: &ShaderConstants) -> Complex {
     let coord = Complex::new(
         frag_coord.x + constants.translate_x + (constants.drag_start_x - constants.drag_end_x),
         frag_coord.y + constants.translate_y + (constants.drag_start_y - constants.drag_end_y),
     );
+    constants.zoom * (coord - 0.5 * Complex::new(constants.width as f32, constants.height as f32))
+        / constants.height as f32
+}
 
-    let uv = constants.zoom
-        * (coord - 0.5 * Complex::new(constants.width as f32, constants.height as f32))
-        / constants.height as f32;
-
-    let mut z = Complex::ZERO;
-    let mut n = ((2.0 + (constants.time * 3.0).cos()) * 10.0) as u32;
-    while z.length() < 2.0 && n > 0 {
-        z = z * z + uv;
-        n -= 1;
+/// Iterates `z = z² + c` for the point `uv`. Returns the final `z` and the number of iterations.
+fn iterate(uv: Complex, constants: &ShaderConstants) -> (Complex, u32) {
+    // The Mandelbrot set varies `c` over the plane, a Julia set the starting point.
+    let (mut z, c) = if constants.julia != 0 {
+        (uv, Complex::new(constants.julia_x, constants.julia_y))
+    } else {
+        (Complex::ZERO, uv)
+    };
+    let mut n = 0;
+    while z.length_squared() < BAILOUT * BAILOUT && n < constants.max_iterations {
+        z = z * z + c;
+        n += 1;
     }
+    (z, n)
+}
 
-    let c = if n == 0 {
+/// The color of a point that took `n` iterations to escape to `z`, or black if it didn't.
+fn color(z: Complex, n: u32, constants: &ShaderConstants) -> Vec4 {
+    let color = if n == constants.max_iterations {
         Vec3::ZERO
     } else {
-        vec3(0.9, 0.6, 0.3)
+        // Normalized iteration count: subtract how far past the bailout radius `z` overshot,
+        // which turns the integer escape time into a continuous one without banding.
+        // `ln |z|` is the real part of `ln z`.
+        let smooth_n = n as f32 - (z.ln().x / BAILOUT.ln()).log2();
+        palette::palette(constants.palette, 0.05 * smooth_n)
     };
-    *output = c.extend(1.0);
+    color.extend(1.0)
+}
+
+// Escaping far beyond the usual radius of 2 is what makes the smoothing accurate.
+const BAILOUT: f32 = 256.0;
+
+/// Iterates `z = z² + c` for `z = Z + dz` and `c = C + dc`, where `Z` is the precomputed
+/// `reference_orbit` for `C`, by only iterating the small difference
+/// `dz' = 2 Z dz + dz² + dc`. Returns the final `z` and the number of iterations.
+///
+/// Once `Z + dz` gets smaller than `dz`, or the reference orbit has escaped, `dz` can no longer
+/// be represented accurately relative to `Z`, which shows up as glitches. In that case the
+/// iteration is rebased, continuing with `dz = z` from the start of the reference orbit.
+fn iterate_perturbed(
+    reference_orbit: &[Vec2],
+    len: usize,
+    mut dz: Complex,
+    dc: Complex,
+    max_iterations: u32,
+) -> (Complex, u32) {
+    let mut z = Complex::from(reference_orbit[0]) + dz;
+    let mut m = 0;
+    let mut n = 0;
+    while z.length_squared() < BAILOUT * BAILOUT && n < max_iterations {
+        dz = 2.0 * Complex::from(reference_orbit[m]) * dz + dz * dz + dc;
+        m += 1;
+        z = Complex::from(reference_orbit[m]) + dz;
+        n += 1;
+        if z.length_squared() < dz.length_squared() || m + 1 >= len {
+            dz = z - Complex::from(reference_orbit[0]);
+            m = 0;
+        }
+    }
+    (z, n)
 }
 
 #[spirv(vertex)]
@@ -0,0 +1,118 @@
+#![cfg_attr(target_arch = "spirv", no_std)]
+
+use camera::Camera;
+use sdf_3d::*;
+use shared::*;
+use spirv_std::glam::{vec3, Vec3, Vec4, Vec4Swizzles};
+#[cfg_attr(not(target_arch = "spirv"), allow(unused_imports))]
+use spirv_std::num_traits::Float;
+use spirv_std::spirv;
+
+const MAX_DISTANCE: f32 = 50.0;
+const MAX_STEPS: u32 = 128;
+
+/// Direction towards the sun.
+const SUN: Vec3 = Vec3::new(0.57735, 0.57735, -0.57735);
+
+const SKY: Vec3 = Vec3::new(0.55, 0.7, 0.9);
+
+#[spirv(fragment)]
+pub fn main_fs(
+    #[spirv(frag_coord)] frag_coord: Vec4,
+    #[spirv(push_constant)] constants: &ShaderConstants,
+    #[spirv(uniform, descriptor_set = 0, binding = 1)] camera: &Camera,
+    output: &mut Vec4,
+) {
+    let direction = camera.ray_direction(frag_coord.xy());
+    let color = render(camera.eye(), direction, constants.time);
+    // Approximate gamma correction, the render target stores the values as they are.
+    *output = color.powf(1.0 / 2.2).extend(1.0);
+}
+
+/// [`main_fs`] for adapters without push constants.
+#[spirv(fragment)]
+pub fn main_fs_uniform(
+    #[spirv(frag_coord)] frag_coord: Vec4,
+    #[spirv(uniform, descriptor_set = 0, binding = 2)] constants: &ShaderConstants,
+    #[spirv(uniform, descriptor_set = 0, binding = 1)] camera: &Camera,
+    output: &mut Vec4,
+) {
+    main_fs(frag_coord, constants, camera, output);
+}
+
+fn blob(p: Vec3, time: f32) -> f32 {
+    let bob = 0.2 * (2.0 * time).sin();
+    let ball = sphere(p - vec3(0.0, 1.2 + bob, 0.0), 0.7);
+    let ring = torus(p - vec3(0.0, 0.6, 0.0), 1.0, 0.25);
+    smooth_min(ball, ring, 0.4)
+}
+
+fn carved_box(p: Vec3) -> f32 {
+    let p = p - vec3(2.6, 0.6, 0.8);
+    subtraction(rounded_cuboid(p, Vec3::splat(0.6), 0.1), sphere(p, 0.75))
+}
+
+fn pillar(p: Vec3) -> f32 {
+    capsule(p, vec3(-2.4, 0.3, 1.2), vec3(-2.4, 2.0, 1.2), 0.3)
+}
+
+fn ground(p: Vec3) -> f32 {
+    plane(p, Vec3::Y, 0.0)
+}
+
+fn scene(p: Vec3, time: f32) -> f32 {
+    union(
+        union(blob(p, time), carved_box(p)),
+        union(pillar(p), ground(p)),
+    )
+}
+
+/// The albedo of whichever surface is closest to `p`.
+fn material(p: Vec3, time: f32) -> Vec3 {
+    let d = scene(p, time);
+    if blob(p, time) <= d {
+        vec3(0.9, 0.3, 0.2)
+    } else if carved_box(p) <= d {
+        vec3(0.2, 0.5, 0.9)
+    } else if pillar(p) <= d {
+        vec3(0.9, 0.8, 0.3)
+    } else {
+        // Checkerboard floor.
+        let checker = ((p.x.floor() + p.z.floor()) as i32 & 1) as f32;
+        vec3(0.35, 0.35, 0.35) + 0.15 * checker
+    }
+}
+
+fn render(eye: Vec3, direction: Vec3, time: f32) -> Vec3 {
+    let sdf = |p| scene(p, time);
+    let sky = SKY - 0.4 * direction.y.max(0.0);
+    let t = raymarch(sdf, eye, direction, MAX_DISTANCE, MAX_STEPS);
+    if t >= MAX_DISTANCE {
+        return sky;
+    }
+
+    let p = eye + direction * t;
+    let n = normal(sdf, p, 1e-3);
+    let albedo = material(p, time);
+
+    let diffuse = n.dot(SUN).max(0.0) * soft_shadow(sdf, p, SUN, 0.02, 10.0, 12.0);
+    let occlusion = ambient_occlusion(sdf, p, n);
+    let sky_light = (0.5 + 0.5 * n.y) * occlusion;
+    let half_vector = (SUN - direction).normalize();
+    let specular = n.dot(half_vector).max(0.0).powf(32.0) * diffuse;
+
+    let light = vec3(1.3, 1.2, 1.0) * diffuse + SKY * 0.4 * sky_light;
+    let color = albedo * light + Vec3::splat(0.3 * specular);
+
+    // Fade into the sky with distance.
+    let fog = 1.0 - (-0.002 * t * t).exp();
+    color.lerp(sky, fog)
+}
+
+#[spirv(vertex)]
+pub fn main_vs(
+    #[spirv(vertex_index)] vert_id: i32,
+    #[spirv(position, invariant)] out_pos: &mut Vec4,
+) {
+    fullscreen_vs(vert_id, out_pos)
+}
@@ -0,0 +1,49 @@
+//! The camera of 3D shaders, set up by the host and bound as a uniform buffer.
+
+use bytemuck::{Pod, Zeroable};
+use spirv_std::glam::{Mat4, Vec2, Vec3, Vec4, Vec4Swizzles};
+
+/// Where the camera is, where it looks and how it projects, in world space.
+///
+/// Only made of `Vec4`s and `Mat4`s, which have the same layout on the host and in uniform
+/// buffers.
+#[derive(Copy, Clone, Pod, Zeroable)]
+#[repr(C)]
+pub struct Camera {
+    /// World to clip space, for shaders that project geometry.
+    pub view_projection: Mat4,
+
+    /// The position of the camera, `w` is unused.
+    pub eye: Vec4,
+
+    /// The camera's axes in world space, `w` is unused. Rays through the center of the
+    /// viewport go along `forward`.
+    pub right: Vec4,
+    pub up: Vec4,
+    pub forward: Vec4,
+
+    /// The top left corner (`xy`) and size (`zw`) of the painted area, in the same physical
+    /// pixels as `frag_coord`.
+    pub viewport: Vec4,
+
+    /// `tan(fov_y / 2)` for the vertical field of view, then the near and far plane of
+    /// `view_projection`, `w` is unused.
+    pub projection: Vec4,
+}
+
+impl Camera {
+    pub fn eye(&self) -> Vec3 {
+        self.eye.xyz()
+    }
+
+    /// The (normalized) direction of the ray from [`Self::eye`] through the pixel at
+    /// `frag_coord`.
+    pub fn ray_direction(&self, frag_coord: Vec2) -> Vec3 {
+        let size = self.viewport.zw();
+        // -1..1 vertically, with y up.
+        let ndc = (2.0 * (frag_coord - self.viewport.xy()) - size) / size.y;
+        let tan_half_fov_y = self.projection.x;
+        (self.forward.xyz() + tan_half_fov_y * (ndc.x * self.right.xyz() - ndc.y * self.up.xyz()))
+            .normalize()
+    }
+}
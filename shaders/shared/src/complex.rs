@@ -1,6 +1,8 @@
 use core::convert::From;
 use core::ops::*;
 use spirv_std::glam::Vec2;
+#[cfg_attr(not(target_arch = "spirv"), allow(unused_imports))]
+use spirv_std::num_traits::Float;
 
 #[derive(Copy, Clone)]
 pub struct Complex(Vec2);
@@ -10,6 +12,103 @@ impl Complex {
         Complex::from(Vec2::new(x, y))
     }
     pub const ZERO: Complex = Complex(Vec2::ZERO);
+    pub const ONE: Complex = Complex(Vec2::X);
+    pub const I: Complex = Complex(Vec2::Y);
+
+    /// The complex number with magnitude `r` and argument `theta`.
+    pub fn from_polar(r: f32, theta: f32) -> Self {
+        Complex::new(r * theta.cos(), r * theta.sin())
+    }
+
+    /// The magnitude and argument, see [`Self::norm`] and [`Self::arg`].
+    pub fn to_polar(self) -> (f32, f32) {
+        (self.norm(), self.arg())
+    }
+
+    pub fn conj(self) -> Self {
+        Complex::new(self.x, -self.y)
+    }
+
+    /// `|z|²`, cheaper than [`Self::norm`] as it needs no square root.
+    pub fn norm_sqr(self) -> f32 {
+        self.x * self.x + self.y * self.y
+    }
+
+    /// `|z|`.
+    pub fn norm(self) -> f32 {
+        // Not `f32::hypot`, which rust-gpu can't compile.
+        self.0.length()
+    }
+
+    /// The angle to the positive real axis, in `(-π, π]`.
+    pub fn arg(self) -> f32 {
+        self.y.atan2(self.x)
+    }
+
+    /// `1 / z`.
+    pub fn recip(self) -> Self {
+        self.conj() / self.norm_sqr()
+    }
+
+    /// `e^z`.
+    pub fn exp(self) -> Self {
+        Complex::from_polar(self.x.exp(), self.y)
+    }
+
+    /// The principal value of the natural logarithm, with the imaginary part in `(-π, π]`.
+    pub fn ln(self) -> Self {
+        Complex::new(self.norm().ln(), self.arg())
+    }
+
+    /// `z^exp` for a real exponent, using the principal value for fractional ones.
+    pub fn powf(self, exp: f32) -> Self {
+        // Including `0^0`, like `f32::powf`.
+        if exp == 0.0 {
+            return Complex::ONE;
+        }
+        if self.x == 0.0 && self.y == 0.0 {
+            return Complex::ZERO;
+        }
+        let (r, theta) = self.to_polar();
+        Complex::from_polar(r.powf(exp), theta * exp)
+    }
+
+    /// `z^exp` for a complex exponent, i.e. `e^(exp ln z)`.
+    pub fn powc(self, exp: Complex) -> Self {
+        if exp.x == 0.0 && exp.y == 0.0 {
+            return Complex::ONE;
+        }
+        if self.x == 0.0 && self.y == 0.0 {
+            return Complex::ZERO;
+        }
+        (exp * self.ln()).exp()
+    }
+
+    /// The principal square root, with a non-negative real part.
+    pub fn sqrt(self) -> Self {
+        // Avoids the cancellation of the polar form near the negative real axis.
+        let r = self.norm();
+        let re = (0.5 * (r + self.x)).sqrt();
+        let im = (0.5 * (r - self.x)).sqrt();
+        Complex::new(re, if self.y < 0.0 { -im } else { im })
+    }
+
+    pub fn sin(self) -> Self {
+        Complex::new(self.x.sin() * self.y.cosh(), self.x.cos() * self.y.sinh())
+    }
+
+    pub fn cos(self) -> Self {
+        Complex::new(self.x.cos() * self.y.cosh(), -self.x.sin() * self.y.sinh())
+    }
+
+    pub fn tanh(self) -> Self {
+        let (x, y) = (2.0 * self.x, 2.0 * self.y);
+        // `cosh(x)` overflows long before `tanh(z)` stops converging to ±1.
+        if x.abs() > 40.0 {
+            return Complex::new(x.signum(), 0.0);
+        }
+        Complex::new(x.sinh(), y.sin()) / (x.cosh() + y.cos())
+    }
 }
 
 impl From<Vec2> for Complex {
@@ -76,3 +175,160 @@ impl Div<f32> for Complex {
         Complex::new(self.x / other, self.y / other)
     }
 }
+
+impl Div for Complex {
+    type Output = Self;
+    fn div(self, other: Self) -> Self::Output {
+        let denominator = other.norm_sqr();
+        Complex::new(
+            (self.x * other.x + self.y * other.y) / denominator,
+            (self.y * other.x - self.x * other.y) / denominator,
+        )
+    }
+}
+
+impl Neg for Complex {
+    type Output = Self;
+    fn neg(self) -> Self::Output {
+        Complex::new(-self.x, -self.y)
+    }
+}
+
+impl AddAssign for Complex {
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
+    }
+}
+
+impl SubAssign for Complex {
+    fn sub_assign(&mut self, other: Self) {
+        *self = *self - other;
+    }
+}
+
+impl MulAssign for Complex {
+    fn mul_assign(&mut self, other: Self) {
+        *self = *self * other;
+    }
+}
+
+impl MulAssign<f32> for Complex {
+    fn mul_assign(&mut self, other: f32) {
+        *self = *self * other;
+    }
+}
+
+impl DivAssign for Complex {
+    fn div_assign(&mut self, other: Self) {
+        *self = *self / other;
+    }
+}
+
+impl DivAssign<f32> for Complex {
+    fn div_assign(&mut self, other: f32) {
+        *self = *self / other;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Reference = num_complex::Complex<f32>;
+
+    /// Deterministic values covering all four quadrants and a range of magnitudes.
+    fn samples() -> impl Iterator<Item = Complex> {
+        let mut state = 0x9e37_79b9_u32;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state as f32 / u32::MAX as f32 * 2.0 - 1.0
+        };
+        (0..500).map(move |i| {
+            let scale = [0.01, 0.5, 1.0, 3.0][i % 4];
+            Complex::new(scale * next(), scale * next())
+        })
+    }
+
+    fn reference(z: Complex) -> Reference {
+        Reference::new(z.x, z.y)
+    }
+
+    #[track_caller]
+    fn assert_close(actual: Complex, expected: Reference) {
+        // Relative to the magnitude of the whole result, as the parts may cancel out.
+        let tolerance = 1e-5 * expected.norm().max(1.0);
+        assert!(
+            (actual.x - expected.re).abs() <= tolerance
+                && (actual.y - expected.im).abs() <= tolerance,
+            "{} + {}i != {expected}",
+            actual.x,
+            actual.y
+        );
+    }
+
+    #[test]
+    fn arithmetic_matches_num_complex() {
+        for (a, b) in samples().zip(samples().skip(1)) {
+            let (x, y) = (reference(a), reference(b));
+            assert_close(a + b, x + y);
+            assert_close(a - b, x - y);
+            assert_close(a * b, x * y);
+            assert_close(a / b, x / y);
+            assert_close(a * 2.5, x * 2.5);
+            assert_close(a / 2.5, x / 2.5);
+            assert_close(-a, -x);
+
+            let assigned = |op: fn(&mut Complex, Complex)| {
+                let mut c = a;
+                op(&mut c, b);
+                reference(c)
+            };
+            assert_eq!(assigned(|c, b| *c += b), reference(a + b));
+            assert_eq!(assigned(|c, b| *c -= b), reference(a - b));
+            assert_eq!(assigned(|c, b| *c *= b), reference(a * b));
+            assert_eq!(assigned(|c, b| *c /= b), reference(a / b));
+            assert_eq!(assigned(|c, _| *c *= 2.5), reference(a * 2.5));
+            assert_eq!(assigned(|c, _| *c /= 2.5), reference(a / 2.5));
+        }
+    }
+
+    #[test]
+    fn functions_match_num_complex() {
+        for (a, b) in samples().zip(samples().skip(1)) {
+            let x = reference(a);
+            assert_close(a.conj(), x.conj());
+            assert!((a.norm_sqr() - x.norm_sqr()).abs() <= 1e-5 * x.norm_sqr().max(1.0));
+            assert!((a.norm() - x.norm()).abs() <= 1e-5 * x.norm().max(1.0));
+            assert!((a.arg() - x.arg()).abs() <= 1e-5);
+            assert_close(a.recip(), x.inv());
+            assert_close(a.exp(), x.exp());
+            assert_close(a.ln(), x.ln());
+            assert_close(a.powf(2.5), x.powf(2.5));
+            assert_close(a.powf(-0.5), x.powf(-0.5));
+            assert_close(a.powf(0.0), x.powf(0.0));
+            assert_close(a.powc(Complex::ZERO), x.powc(Reference::new(0.0, 0.0)));
+            assert_close(a.powc(b), x.powc(reference(b)));
+            assert_close(a.sqrt(), x.sqrt());
+            assert_close(a.sin(), x.sin());
+            assert_close(a.cos(), x.cos());
+            assert_close(a.tanh(), x.tanh());
+
+            let (r, theta) = a.to_polar();
+            assert_close(Complex::from_polar(r, theta), x);
+        }
+    }
+
+    #[test]
+    fn special_values() {
+        assert_close(Complex::I * Complex::I, Reference::new(-1.0, 0.0));
+        assert_close(Complex::ZERO.powf(2.0), Reference::new(0.0, 0.0));
+        let zero = Reference::new(0.0, 0.0);
+        assert_close(Complex::ZERO.powf(0.0), zero.powf(0.0));
+        assert_close(Complex::ZERO.powc(Complex::ZERO), zero.powc(zero));
+        assert_close(Complex::new(-4.0, 0.0).sqrt(), Reference::new(0.0, 2.0));
+        assert_close(Complex::new(100.0, 1.0).tanh(), Reference::new(1.0, 0.0));
+        assert_close(Complex::new(-100.0, 1.0).tanh(), Reference::new(-1.0, 0.0));
+    }
+}
@@ -1,8 +1,10 @@
 #![cfg_attr(target_arch = "spirv", no_std, feature(lang_items))]
 
+pub mod camera;
 pub mod complex;
-// pub mod sdf_2d;
-// pub mod sdf_3d;
+pub mod palette;
+pub mod sdf_2d;
+pub mod sdf_3d;
 
 use bytemuck::{Pod, Zeroable};
 use spirv_std::glam::{vec2, Vec2, Vec4};
@@ -10,6 +12,11 @@ use spirv_std::glam::{vec2, Vec2, Vec4};
 pub const SQRT_3: f32 = 1.732050807568877293527446341505872367;
 pub use core::f32::consts::PI;
 
+/// The per-frame parameters of every shader.
+///
+/// Fragment shaders read them from push constants in `main_fs`, and from a uniform buffer at
+/// `descriptor_set = 0, binding = 2` in `main_fs_uniform`, which the host uses instead when
+/// the adapter doesn't support push constants.
 #[derive(Copy, Clone, Pod, Zeroable)]
 #[repr(C)]
 pub struct ShaderConstants {
@@ -17,8 +24,16 @@ pub struct ShaderConstants {
     pub height: u32,
     pub time: f32,
 
+    /// Position of the mouse in physical pixels, relative to the top left corner
+    /// of the painted area.
     pub cursor_x: f32,
     pub cursor_y: f32,
+
+    /// Where the current (or most recent) drag started and where it is now (or ended),
+    /// in the same coordinates as `cursor_x`/`cursor_y`.
+    ///
+    /// The host only folds a drag into `translate_x`/`translate_y` once the next one
+    /// starts, so panning shaders should offset by `translate + drag_start - drag_end`.
     pub drag_start_x: f32,
     pub drag_start_y: f32,
     pub drag_end_x: f32,
@@ -34,8 +49,68 @@ pub struct ShaderConstants {
     /// or `f32::NEG_INFINITY` for buttons which haven't been pressed yet.
     ///
     /// If this is the first frame after the press of some button, that button's
-    /// entry in `mouse_button_press_time` will exactly equal `time`.
-    pub mouse_button_press_time: [f32; 3],
+    /// `mouse_button_press_time_*` will exactly equal `time`.
+    ///
+    /// Not an array, arrays of scalars have a different layout in uniform buffers than in push
+    /// constants.
+    pub mouse_button_press_time_left: f32,
+    pub mouse_button_press_time_middle: f32,
+    pub mouse_button_press_time_right: f32,
+
+    /// Which of the [`palette`]s to colour with.
+    pub palette: u32,
+
+    /// Iteration limit for escape-time fractals.
+    pub max_iterations: u32,
+
+    /// Non-zero to draw the Julia set for `julia_x + julia_y i` instead of the Mandelbrot set.
+    pub julia: u32,
+    pub julia_x: f32,
+    pub julia_y: f32,
+
+    /// Number of points in the reference orbit storage buffer. Non-zero means escape-time
+    /// shaders should iterate the difference to that orbit (perturbation theory) instead of
+    /// the pixel's own coordinates, which are then relative to the orbit's starting point.
+    pub reference_orbit_len: u32,
+}
+
+/// The size of push constants every Vulkan implementation supports. The host switches to a
+/// uniform buffer on devices with less, but keeping `ShaderConstants` within this keeps push
+/// constants usable everywhere they are supported at all.
+const MIN_MAX_PUSH_CONSTANT_SIZE: usize = 128;
+
+const _: () = assert!(
+    core::mem::size_of::<ShaderConstants>() <= MIN_MAX_PUSH_CONSTANT_SIZE,
+    "`ShaderConstants` no longer fits into the push constants guaranteed by Vulkan"
+);
+
+impl ShaderConstants {
+    /// Constants for a `width` by `height` frame at the start of time, with nothing zoomed,
+    /// panned or pressed.
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            zoom: 1.0,
+            mouse_button_press_time_left: f32::NEG_INFINITY,
+            mouse_button_press_time_middle: f32::NEG_INFINITY,
+            mouse_button_press_time_right: f32::NEG_INFINITY,
+            palette: palette::RAINBOW,
+            max_iterations: 100,
+            ..Zeroable::zeroed()
+        }
+    }
+
+    /// Sets `mouse_button_press_time_*` of the `button` with the same index as in
+    /// `mouse_button_pressed`, ignoring buttons other than those three.
+    pub fn set_mouse_button_press_time(&mut self, button: usize, time: f32) {
+        match button {
+            0 => self.mouse_button_press_time_left = time,
+            1 => self.mouse_button_press_time_middle = time,
+            2 => self.mouse_button_press_time_right = time,
+            _ => {}
+        }
+    }
 }
 
 pub fn fullscreen_vs(vert_id: i32, out_pos: &mut Vec4) {
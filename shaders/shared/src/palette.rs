@@ -0,0 +1,78 @@
+//! Colour palettes mapping a (wrapping) parameter `t` to a colour.
+
+use spirv_std::glam::{vec3, Vec3};
+#[cfg_attr(not(target_arch = "spirv"), allow(unused_imports))]
+use spirv_std::num_traits::Float;
+
+use crate::PI;
+
+pub const RAINBOW: u32 = 0;
+pub const SUNSET: u32 = 1;
+pub const OCEAN: u32 = 2;
+pub const CANDY: u32 = 3;
+pub const ULTRA_FRACTAL: u32 = 4;
+
+/// Number of palettes understood by [`palette`].
+pub const COUNT: u32 = 5;
+
+/// Looks up `t` in one of the palettes above, repeating with a period of 1.
+pub fn palette(palette: u32, t: f32) -> Vec3 {
+    match palette {
+        SUNSET => cosine(
+            t,
+            vec3(0.5, 0.5, 0.5),
+            vec3(0.5, 0.5, 0.5),
+            vec3(1.0, 1.0, 1.0),
+            vec3(0.3, 0.2, 0.2),
+        ),
+        OCEAN => cosine(
+            t,
+            vec3(0.5, 0.5, 0.5),
+            vec3(0.5, 0.5, 0.5),
+            vec3(1.0, 0.7, 0.4),
+            vec3(0.0, 0.15, 0.2),
+        ),
+        CANDY => cosine(
+            t,
+            vec3(0.8, 0.5, 0.4),
+            vec3(0.2, 0.4, 0.2),
+            vec3(2.0, 1.0, 1.0),
+            vec3(0.0, 0.25, 0.25),
+        ),
+        ULTRA_FRACTAL => ultra_fractal(t),
+        _ => cosine(
+            t,
+            vec3(0.5, 0.5, 0.5),
+            vec3(0.5, 0.5, 0.5),
+            vec3(1.0, 1.0, 1.0),
+            vec3(0.0, 0.33, 0.67),
+        ),
+    }
+}
+
+/// Procedural palette `a + b * cos(2π * (c * t + d))`,
+/// see <https://iquilezles.org/articles/palettes/>.
+pub fn cosine(t: f32, a: Vec3, b: Vec3, c: Vec3, d: Vec3) -> Vec3 {
+    let phase = 2.0 * PI * (c * t + d);
+    a + b * vec3(phase.x.cos(), phase.y.cos(), phase.z.cos())
+}
+
+/// Piecewise linear gradient resembling the default Ultra Fractal colouring.
+pub fn ultra_fractal(t: f32) -> Vec3 {
+    let stops = [
+        (0.0, vec3(0.0, 0.027, 0.392)),
+        (0.16, vec3(0.125, 0.42, 0.796)),
+        (0.42, vec3(0.929, 1.0, 1.0)),
+        (0.6425, vec3(1.0, 0.667, 0.0)),
+        (0.8575, vec3(0.0, 0.008, 0.0)),
+        (1.0, vec3(0.0, 0.027, 0.392)),
+    ];
+    let t = t - t.floor();
+    let mut i = 1;
+    while i < stops.len() - 1 && t > stops[i].0 {
+        i += 1;
+    }
+    let (t0, c0) = stops[i - 1];
+    let (t1, c1) = stops[i];
+    c0.lerp(c1, (t - t0) / (t1 - t0))
+}
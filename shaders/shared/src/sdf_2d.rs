@@ -0,0 +1,258 @@
+//! Signed distance functions of 2D shapes, and ways to combine and draw them.
+//!
+//! Distances are negative inside a shape. Most of these follow
+//! <https://iquilezles.org/articles/distfunctions2d/>.
+
+use spirv_std::glam::{vec2, Vec2};
+#[cfg_attr(not(target_arch = "spirv"), allow(unused_imports))]
+use spirv_std::num_traits::Float;
+
+use crate::{smoothstep, SQRT_3};
+
+pub fn circle(p: Vec2, radius: f32) -> f32 {
+    p.length() - radius
+}
+
+/// An axis aligned rectangle centered on the origin.
+pub fn rectangle(p: Vec2, half_size: Vec2) -> f32 {
+    let d = p.abs() - half_size;
+    d.max(Vec2::ZERO).length() + d.x.max(d.y).min(0.0)
+}
+
+/// Like [`rectangle`], with corners rounded by `radius`, but the same outer size.
+pub fn rounded_rectangle(p: Vec2, half_size: Vec2, radius: f32) -> f32 {
+    rectangle(p, half_size - Vec2::splat(radius)) - radius
+}
+
+/// Unsigned distance to the line segment from `a` to `b`.
+pub fn segment(p: Vec2, a: Vec2, b: Vec2) -> f32 {
+    let pa = p - a;
+    let ba = b - a;
+    let h = (pa.dot(ba) / ba.dot(ba)).clamp(0.0, 1.0);
+    (pa - ba * h).length()
+}
+
+/// A closed polygon through `vertices`, which may be concave but must not self-intersect.
+pub fn polygon<const N: usize>(p: Vec2, vertices: &[Vec2; N]) -> f32 {
+    let mut distance_sqr = (p - vertices[0]).length_squared();
+    let mut sign = 1.0;
+    let mut i = 0;
+    let mut j = N - 1;
+    while i < N {
+        let edge = vertices[j] - vertices[i];
+        let w = p - vertices[i];
+        let b = w - edge * (w.dot(edge) / edge.dot(edge)).clamp(0.0, 1.0);
+        distance_sqr = distance_sqr.min(b.length_squared());
+        // Count crossings of a ray to the right of `p`, to tell inside from outside.
+        let c = [
+            p.y >= vertices[i].y,
+            p.y < vertices[j].y,
+            edge.x * w.y > edge.y * w.x,
+        ];
+        if (c[0] && c[1] && c[2]) || !(c[0] || c[1] || c[2]) {
+            sign = -sign;
+        }
+        j = i;
+        i += 1;
+    }
+    sign * distance_sqr.sqrt()
+}
+
+/// Unsigned distance to the quadratic Bézier curve from `a` to `c` with control point `b`.
+pub fn quadratic_bezier(p: Vec2, a: Vec2, b: Vec2, c: Vec2) -> f32 {
+    let e = b - a;
+    let f = a - 2.0 * b + c;
+    if f.length_squared() < 1e-10 {
+        // A straight line, for which the cubic below degenerates.
+        return segment(p, a, c);
+    }
+    let g = 2.0 * e;
+    let d = a - p;
+
+    // The closest point is a root of a cubic in the curve parameter `t`, solve it in
+    // depressed form `t³ + pt + q`.
+    let kk = 1.0 / f.dot(f);
+    let kx = kk * e.dot(f);
+    let ky = kk * (2.0 * e.dot(e) + d.dot(f)) / 3.0;
+    let kz = kk * d.dot(e);
+    let depressed_p = ky - kx * kx;
+    let depressed_q = kx * (2.0 * kx * kx - 3.0 * ky) + kz;
+    let h = depressed_q * depressed_q + 4.0 * depressed_p * depressed_p * depressed_p;
+    let point_at = |t: f32| d + (g + f * t) * t;
+    let distance_sqr = if h >= 0.0 {
+        // One real root.
+        let h = h.sqrt();
+        let x = (vec2(h, -h) - Vec2::splat(depressed_q)) / 2.0;
+        let uv = vec2(cbrt(x.x), cbrt(x.y));
+        let t = (uv.x + uv.y - kx).clamp(0.0, 1.0);
+        point_at(t).length_squared()
+    } else {
+        // Three real roots, but only two of them can be the closest.
+        let z = (-depressed_p).sqrt();
+        let v = (depressed_q / (depressed_p * z * 2.0)).acos() / 3.0;
+        let m = v.cos();
+        let n = v.sin() * SQRT_3;
+        let t0 = ((m + m) * z - kx).clamp(0.0, 1.0);
+        let t1 = ((-n - m) * z - kx).clamp(0.0, 1.0);
+        point_at(t0)
+            .length_squared()
+            .min(point_at(t1).length_squared())
+    };
+    distance_sqr.sqrt()
+}
+
+/// The real cube root, also of negative `x`. rust-gpu lowers `f32::cbrt` to a `pow` by `1/3`,
+/// which is undefined for negative bases.
+fn cbrt(x: f32) -> f32 {
+    x.signum() * x.abs().powf(1.0 / 3.0)
+}
+
+pub fn union(a: f32, b: f32) -> f32 {
+    a.min(b)
+}
+
+pub fn intersection(a: f32, b: f32) -> f32 {
+    a.max(b)
+}
+
+/// `a` with `b` cut out of it.
+pub fn subtraction(a: f32, b: f32) -> f32 {
+    a.max(-b)
+}
+
+/// [`union`] with the seam blended over a distance of about `k`.
+pub fn smooth_min(a: f32, b: f32, k: f32) -> f32 {
+    let h = (k - (a - b).abs()).max(0.0) / k;
+    a.min(b) - h * h * k * 0.25
+}
+
+/// [`intersection`] with the seam blended over a distance of about `k`.
+pub fn smooth_max(a: f32, b: f32, k: f32) -> f32 {
+    -smooth_min(-a, -b, k)
+}
+
+/// Folds the plane into a grid of cells of size `period`, so that a shape around the origin is
+/// repeated in every cell. The shape must fit inside a cell for the distance to stay correct.
+pub fn repeat(p: Vec2, period: Vec2) -> Vec2 {
+    p - period * (p / period).round()
+}
+
+/// Like [`repeat`], but only `limit` cells away from the origin in each direction.
+pub fn repeat_limited(p: Vec2, period: Vec2, limit: Vec2) -> Vec2 {
+    p - period * (p / period).round().clamp(-limit, limit)
+}
+
+/// Coverage (0 outside, 1 inside) of the shape at `distance`, with the edge smoothed over
+/// `pixel_size`, i.e. the size of a pixel in the units of `distance`.
+pub fn fill(distance: f32, pixel_size: f32) -> f32 {
+    1.0 - smoothstep(-0.5 * pixel_size, 0.5 * pixel_size, distance)
+}
+
+/// Coverage of an outline of the shape at `distance`, `width` wide and centered on its edge.
+pub fn stroke(distance: f32, width: f32, pixel_size: f32) -> f32 {
+    fill(distance.abs() - 0.5 * width, pixel_size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[track_caller]
+    fn assert_close(actual: f32, expected: f32) {
+        assert!((actual - expected).abs() < 1e-4, "{actual} != {expected}");
+    }
+
+    #[test]
+    fn primitives() {
+        assert_close(circle(vec2(3.0, 4.0), 2.0), 3.0);
+        assert_close(circle(Vec2::ZERO, 2.0), -2.0);
+
+        let half_size = vec2(2.0, 1.0);
+        assert_close(rectangle(vec2(5.0, 0.0), half_size), 3.0);
+        assert_close(rectangle(vec2(5.0, 5.0), half_size), 5.0);
+        assert_close(rectangle(vec2(0.0, 0.5), half_size), -0.5);
+        assert_close(rounded_rectangle(vec2(5.0, 0.0), half_size, 0.5), 3.0);
+        assert_close(
+            rounded_rectangle(vec2(3.0, 2.0), half_size, 0.5),
+            vec2(1.5, 1.5).length() - 0.5,
+        );
+
+        assert_close(segment(vec2(1.0, 1.0), Vec2::ZERO, vec2(2.0, 0.0)), 1.0);
+        assert_close(segment(vec2(-3.0, 4.0), Vec2::ZERO, vec2(2.0, 0.0)), 5.0);
+    }
+
+    #[test]
+    fn polygon_matches_rectangle() {
+        let half_size = vec2(2.0, 1.0);
+        let vertices = [
+            vec2(-2.0, -1.0),
+            vec2(2.0, -1.0),
+            vec2(2.0, 1.0),
+            vec2(-2.0, 1.0),
+        ];
+        for p in [
+            vec2(0.0, 0.0),
+            vec2(5.0, 0.5),
+            vec2(-3.0, 4.0),
+            vec2(1.5, -0.5),
+        ] {
+            assert_close(polygon(p, &vertices), rectangle(p, half_size));
+        }
+    }
+
+    #[test]
+    fn bezier_matches_sampling() {
+        let (a, b, c) = (vec2(-1.0, 0.0), vec2(0.0, 2.0), vec2(1.0, 0.0));
+        for p in [
+            vec2(0.0, 0.0),
+            vec2(0.0, 3.0),
+            vec2(2.0, -1.0),
+            vec2(0.3, 0.8),
+            // One real root, taking the cube root of a negative number.
+            vec2(-2.0, 1.0),
+        ] {
+            let sampled = (0..=10_000)
+                .map(|i| {
+                    let t = i as f32 / 10_000.0;
+                    let q = a.lerp(b, t).lerp(b.lerp(c, t), t);
+                    (p - q).length()
+                })
+                .fold(f32::INFINITY, f32::min);
+            assert!((quadratic_bezier(p, a, b, c) - sampled).abs() < 1e-3);
+        }
+        // Degenerates to a straight line.
+        assert_close(
+            quadratic_bezier(vec2(0.0, 1.0), a, Vec2::ZERO, c),
+            segment(vec2(0.0, 1.0), a, c),
+        );
+    }
+
+    #[test]
+    fn cube_root_of_negative() {
+        assert_close(cbrt(-8.0), -2.0);
+        assert_close(cbrt(-0.001), -0.1);
+        assert_close(cbrt(27.0), 3.0);
+        assert_close(cbrt(0.0), 0.0);
+    }
+
+    #[test]
+    fn combinations() {
+        assert_close(smooth_min(1.0, 5.0, 0.5), 1.0);
+        assert!(smooth_min(1.0, 1.0, 0.5) < 1.0);
+        assert_close(smooth_max(1.0, 5.0, 0.5), 5.0);
+        assert_close(subtraction(-1.0, -0.5), 0.5);
+
+        let period = vec2(2.0, 3.0);
+        assert_close(repeat(vec2(4.5, 3.5), period).x, 0.5);
+        assert_close(repeat(vec2(4.5, 3.5), period).y, 0.5);
+        assert_close(
+            repeat_limited(vec2(10.5, 0.0), period, vec2(2.0, 2.0)).x,
+            6.5,
+        );
+
+        assert_close(fill(-1.0, 0.1), 1.0);
+        assert_close(fill(1.0, 0.1), 0.0);
+        assert_close(fill(0.0, 0.1), 0.5);
+        assert_close(stroke(0.0, 0.2, 0.1), 1.0);
+    }
+}
@@ -0,0 +1,187 @@
+//! Signed distance functions of 3D shapes, and the raymarching and shading built on them.
+//!
+//! Distances are negative inside a shape. Most of these follow
+//! <https://iquilezles.org/articles/distfunctions/>. Scenes are passed around as closures
+//! mapping a point to its distance from the nearest surface.
+
+use spirv_std::glam::{vec2, vec3, Vec3};
+#[cfg_attr(not(target_arch = "spirv"), allow(unused_imports))]
+use spirv_std::num_traits::Float;
+
+pub use crate::sdf_2d::{intersection, smooth_max, smooth_min, subtraction, union};
+
+pub fn sphere(p: Vec3, radius: f32) -> f32 {
+    p.length() - radius
+}
+
+/// An axis aligned box centered on the origin.
+pub fn cuboid(p: Vec3, half_size: Vec3) -> f32 {
+    let d = p.abs() - half_size;
+    d.max(Vec3::ZERO).length() + d.x.max(d.y.max(d.z)).min(0.0)
+}
+
+/// Like [`cuboid`], with edges rounded by `radius`, but the same outer size.
+pub fn rounded_cuboid(p: Vec3, half_size: Vec3, radius: f32) -> f32 {
+    cuboid(p, half_size - Vec3::splat(radius)) - radius
+}
+
+/// A torus around the y axis, `major_radius` from its center to the middle of the tube, with a
+/// tube of `minor_radius`.
+pub fn torus(p: Vec3, major_radius: f32, minor_radius: f32) -> f32 {
+    vec2(vec2(p.x, p.z).length() - major_radius, p.y).length() - minor_radius
+}
+
+/// The line segment from `a` to `b`, thickened by `radius`.
+pub fn capsule(p: Vec3, a: Vec3, b: Vec3, radius: f32) -> f32 {
+    let pa = p - a;
+    let ba = b - a;
+    let h = (pa.dot(ba) / ba.dot(ba)).clamp(0.0, 1.0);
+    (pa - ba * h).length() - radius
+}
+
+/// A cylinder around the y axis, from `-half_height` to `half_height`.
+pub fn cylinder(p: Vec3, radius: f32, half_height: f32) -> f32 {
+    let d = vec2(vec2(p.x, p.z).length(), p.y).abs() - vec2(radius, half_height);
+    d.x.max(d.y).min(0.0) + d.max(vec2(0.0, 0.0)).length()
+}
+
+/// The half space below the plane through the origin with the (normalized) `normal`,
+/// offset by `height` along it.
+pub fn plane(p: Vec3, normal: Vec3, height: f32) -> f32 {
+    p.dot(normal) - height
+}
+
+/// The surface normal at `p`, as the gradient of `scene` estimated with four samples
+/// `epsilon` apart.
+pub fn normal(scene: impl Fn(Vec3) -> f32, p: Vec3, epsilon: f32) -> Vec3 {
+    // Sampling the corners of a tetrahedron needs one evaluation less than central differences.
+    let k0 = vec3(1.0, -1.0, -1.0);
+    let k1 = vec3(-1.0, -1.0, 1.0);
+    let k2 = vec3(-1.0, 1.0, -1.0);
+    let k3 = vec3(1.0, 1.0, 1.0);
+    (k0 * scene(p + epsilon * k0)
+        + k1 * scene(p + epsilon * k1)
+        + k2 * scene(p + epsilon * k2)
+        + k3 * scene(p + epsilon * k3))
+    .normalize()
+}
+
+/// Sphere traces `scene` along the ray from `origin` in `direction` (normalized), returning the
+/// distance to the first surface hit, or `max_distance` if there is none.
+pub fn raymarch(
+    scene: impl Fn(Vec3) -> f32,
+    origin: Vec3,
+    direction: Vec3,
+    max_distance: f32,
+    max_steps: u32,
+) -> f32 {
+    let mut t = 0.0;
+    let mut step = 0;
+    while step < max_steps && t < max_distance {
+        let d = scene(origin + direction * t);
+        // Scale the tolerance with distance, further away a pixel covers more of the scene.
+        if d.abs() < 1e-4 * t.max(1.0) {
+            return t;
+        }
+        t += d;
+        step += 1;
+    }
+    max_distance
+}
+
+/// How much light from `direction` (normalized) reaches `origin`, between 0 (fully in shadow)
+/// and 1, with penumbras that get softer the smaller `sharpness` is.
+pub fn soft_shadow(
+    scene: impl Fn(Vec3) -> f32,
+    origin: Vec3,
+    direction: Vec3,
+    min_distance: f32,
+    max_distance: f32,
+    sharpness: f32,
+) -> f32 {
+    let mut light: f32 = 1.0;
+    let mut t = min_distance;
+    let mut step = 0;
+    while step < 64 && t < max_distance {
+        let d = scene(origin + direction * t);
+        if d < 1e-4 {
+            return 0.0;
+        }
+        // How close the ray passes by an occluder, relative to how far along it is.
+        light = light.min(sharpness * d / t);
+        t += d.clamp(0.01, 0.2);
+        step += 1;
+    }
+    light.clamp(0.0, 1.0)
+}
+
+/// Ambient occlusion at the point `p` with surface `normal`, between 0 (fully occluded) and 1,
+/// from how much closer nearby surfaces are than they would be on an open plane.
+pub fn ambient_occlusion(scene: impl Fn(Vec3) -> f32, p: Vec3, normal: Vec3) -> f32 {
+    let mut occlusion = 0.0;
+    let mut weight = 1.0;
+    let mut i = 0;
+    while i < 5 {
+        let h = 0.01 + 0.12 * i as f32 / 4.0;
+        occlusion += (h - scene(p + h * normal)) * weight;
+        weight *= 0.95;
+        i += 1;
+    }
+    (1.0 - 3.0 * occlusion).clamp(0.0, 1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[track_caller]
+    fn assert_close(actual: f32, expected: f32) {
+        assert!((actual - expected).abs() < 1e-3, "{actual} != {expected}");
+    }
+
+    fn scene(p: Vec3) -> f32 {
+        union(sphere(p - vec3(0.0, 1.0, 0.0), 1.0), plane(p, Vec3::Y, 0.0))
+    }
+
+    #[test]
+    fn primitives() {
+        assert_close(sphere(vec3(0.0, 3.0, 4.0), 1.0), 4.0);
+        assert_close(cuboid(vec3(3.0, 0.0, 0.0), Vec3::ONE), 2.0);
+        assert_close(cuboid(Vec3::ZERO, Vec3::ONE), -1.0);
+        assert_close(rounded_cuboid(vec3(3.0, 0.0, 0.0), Vec3::ONE, 0.5), 2.0);
+        assert_close(torus(vec3(2.0, 1.0, 0.0), 2.0, 0.5), 0.5);
+        assert_close(capsule(vec3(0.0, 5.0, 0.0), Vec3::ZERO, Vec3::Y, 0.5), 3.5);
+        assert_close(cylinder(vec3(3.0, 0.0, 0.0), 1.0, 1.0), 2.0);
+        assert_close(cylinder(vec3(0.0, 3.0, 0.0), 1.0, 1.0), 2.0);
+        assert_close(plane(vec3(1.0, 2.0, 3.0), Vec3::Y, 0.5), 1.5);
+    }
+
+    #[test]
+    fn raymarching() {
+        let t = raymarch(scene, vec3(0.0, 1.0, -5.0), Vec3::Z, 100.0, 128);
+        assert_close(t, 4.0);
+        assert_close(
+            raymarch(scene, vec3(0.0, 1.0, -5.0), Vec3::Y, 100.0, 128),
+            100.0,
+        );
+
+        let n = normal(scene, vec3(0.0, 1.0, -1.0), 1e-3);
+        assert!(n.dot(-Vec3::Z) > 0.999);
+        let n = normal(scene, vec3(5.0, 0.0, 5.0), 1e-3);
+        assert!(n.dot(Vec3::Y) > 0.999);
+    }
+
+    #[test]
+    fn lighting() {
+        // Right below the sphere the light from above is blocked, far away from it it isn't.
+        let lit = soft_shadow(scene, vec3(5.0, 0.01, 0.0), Vec3::Y, 0.01, 10.0, 8.0);
+        let shadowed = soft_shadow(scene, vec3(0.0, 0.01, 0.5), Vec3::Y, 0.01, 10.0, 8.0);
+        assert_close(lit, 1.0);
+        assert_close(shadowed, 0.0);
+
+        let open = ambient_occlusion(scene, vec3(5.0, 0.0, 0.0), Vec3::Y);
+        let corner = ambient_occlusion(scene, vec3(0.0, 0.0, 0.3), Vec3::Y);
+        assert_close(open, 1.0);
+        assert!(corner < open);
+    }
+}